@@ -0,0 +1,35 @@
+use super::*;
+
+use crate::error::{Diagnostic, Report};
+use crate::source;
+
+pub fn unbound_variable<'src>(span: lexer::Span<'src>, name: &str) -> Diagnostic<'src> {
+    Diagnostic::error("E0005", format!("unbound variable `{name}`"), span)
+}
+
+pub type ScopeResult<'src, T> = Result<T, ScopeErrorVec<'src>>;
+
+#[derive(Debug)]
+pub struct ScopeErrorVec<'src> {
+    pub tokens: &'src lexer::TokenVec<'src>,
+    pub errors: Vec<Diagnostic<'src>>,
+}
+
+impl Report for ScopeErrorVec<'_> {
+    fn source(&self) -> &source::Source {
+        self.tokens.source
+    }
+
+    fn diagnostics(&self) -> &[Diagnostic<'_>] {
+        &self.errors
+    }
+}
+
+impl std::fmt::Display for ScopeErrorVec<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for error in &self.errors {
+            error.print(f, self.tokens)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,306 @@
+mod error;
+
+use crate::{eval, lexer, parser::ast};
+
+use std::collections::HashSet;
+
+pub use error::{ScopeErrorVec, ScopeResult};
+
+pub type DbRef = std::rc::Rc<DbTerm>;
+
+// Nameless form of a term: a bound variable is the number of abstractions
+// between it and its binder, so alpha-equivalent terms are equal and hash alike
+#[derive(Debug, PartialEq, Eq, Hash)]
+pub enum DbTerm {
+    Bound(usize),
+    Global(eval::Name),
+    Num(u64),
+    Bool(bool),
+    Fix,
+    Not(DbRef),
+    Binary(eval::BinOp, DbRef, DbRef),
+    App(DbRef, DbRef),
+    Abs(Hint, DbRef),
+    If(DbRef, DbRef, DbRef),
+}
+
+// Original name of a binder, only kept to convert back to a named term. It
+// never takes part in comparisons or hashing
+#[derive(Debug, Clone)]
+pub struct Hint(pub eval::Name);
+
+impl PartialEq for Hint {
+    fn eq(&self, _other: &Self) -> bool {
+        true
+    }
+}
+
+impl Eq for Hint {}
+
+impl std::hash::Hash for Hint {
+    fn hash<H: std::hash::Hasher>(&self, _state: &mut H) {}
+}
+
+// Lowers a parsed expression. Free variables must be globals, as told by
+// `is_global`, otherwise they are reported at their place in the source
+pub fn lower<'src>(
+    tokens: &'src lexer::TokenVec<'src>,
+    expr: &ast::Node<'src>,
+    is_global: impl Fn(&str) -> bool,
+) -> ScopeResult<'src, DbRef> {
+    let mut lowering = Lowering {
+        is_global: &is_global,
+        bound: Vec::new(),
+        errors: Vec::new(),
+    };
+    let term = lowering.lower(expr);
+    if lowering.errors.is_empty() {
+        Ok(term)
+    } else {
+        Err(ScopeErrorVec {
+            tokens,
+            errors: lowering.errors,
+        })
+    }
+}
+
+struct Lowering<'src, 'a> {
+    is_global: &'a dyn Fn(&str) -> bool,
+    // Names of the enclosing abstractions, innermost last
+    bound: Vec<&'src str>,
+    errors: Vec<crate::error::Diagnostic<'src>>,
+}
+
+impl<'src> Lowering<'src, '_> {
+    fn lower(&mut self, expr: &ast::Node<'src>) -> DbRef {
+        match &*expr.ast {
+            ast::Ast::Var(name) => DbRef::new(self.lower_var(name, expr.span)),
+            ast::Ast::Num(num) => DbRef::new(DbTerm::Num(*num)),
+            ast::Ast::Fix => DbRef::new(DbTerm::Fix),
+            ast::Ast::Not(expr) => DbRef::new(DbTerm::Not(self.lower(expr))),
+            ast::Ast::App(func, arg) => DbRef::new(DbTerm::App(self.lower(func), self.lower(arg))),
+            ast::Ast::Mul(lhs, rhs) => self.lower_binary(eval::BinOp::Mul, lhs, rhs),
+            ast::Ast::Div(lhs, rhs) => self.lower_binary(eval::BinOp::Div, lhs, rhs),
+            ast::Ast::Add(lhs, rhs) => self.lower_binary(eval::BinOp::Add, lhs, rhs),
+            ast::Ast::Sub(lhs, rhs) => self.lower_binary(eval::BinOp::Sub, lhs, rhs),
+            ast::Ast::And(lhs, rhs) => self.lower_binary(eval::BinOp::And, lhs, rhs),
+            ast::Ast::Or(lhs, rhs) => self.lower_binary(eval::BinOp::Or, lhs, rhs),
+            ast::Ast::Eq(lhs, rhs) => self.lower_binary(eval::BinOp::Eq, lhs, rhs),
+            ast::Ast::Neq(lhs, rhs) => self.lower_binary(eval::BinOp::Neq, lhs, rhs),
+            ast::Ast::Abs(arg, _, body) => {
+                self.bound.push(arg);
+                let body = self.lower(body);
+                self.bound.pop();
+                DbRef::new(DbTerm::Abs(Hint(eval::Name::from(*arg)), body))
+            }
+            ast::Ast::Let(bindings, body) | ast::Ast::Where(body, bindings) => {
+                self.lower_let(bindings, body)
+            }
+            ast::Ast::LetRec(bindings, body) => self.lower_let_rec(bindings, body),
+            ast::Ast::If(condition, then, otherwise) => DbRef::new(DbTerm::If(
+                self.lower(condition),
+                self.lower(then),
+                self.lower(otherwise),
+            )),
+            ast::Ast::Assign(..)
+            | ast::Ast::Signature(..)
+            | ast::Ast::Comment(_)
+            | ast::Ast::Source(_) => {
+                unreachable!("assignments are not expressions")
+            }
+            ast::Ast::Error => unreachable!("trees with syntax errors are not lowered"),
+        }
+    }
+
+    fn lower_binary(
+        &mut self,
+        op: eval::BinOp,
+        lhs: &ast::Node<'src>,
+        rhs: &ast::Node<'src>,
+    ) -> DbRef {
+        DbRef::new(DbTerm::Binary(op, self.lower(lhs), self.lower(rhs)))
+    }
+
+    // `let x = e1 in e2` is `(λx. e2) e1`, one binding after the other
+    fn lower_let(&mut self, bindings: &[ast::Node<'src>], body: &ast::Node<'src>) -> DbRef {
+        let Some((binding, rest)) = bindings.split_first() else {
+            return self.lower(body);
+        };
+        let (name, value) = binding.binding();
+        let value = self.lower(value);
+        self.bound.push(name);
+        let body = self.lower_let(rest, body);
+        self.bound.pop();
+        let abs = DbRef::new(DbTerm::Abs(Hint(eval::Name::from(name)), body));
+        DbRef::new(DbTerm::App(abs, value))
+    }
+
+    // `let rec f = e1 in e2` is `(λf. e2) (fix (λf. e1))`, and a group of
+    // bindings is lowered as in `Term::from_ast`
+    fn lower_let_rec(&mut self, bindings: &[ast::Node<'src>], body: &ast::Node<'src>) -> DbRef {
+        let names: Vec<&'src str> = bindings.iter().map(|binding| binding.binding().0).collect();
+        let depth = self.bound.len();
+        self.bound.extend(&names);
+        let mut term = self.lower(body);
+        // The values are copied for each other, so their errors are only
+        // reported once, from where all the names are in scope
+        for binding in bindings {
+            self.lower(binding.binding().1);
+        }
+        let errors = self.errors.len();
+        self.bound.truncate(depth);
+
+        for name in names.iter().rev() {
+            term = DbRef::new(DbTerm::Abs(Hint(eval::Name::from(*name)), term));
+        }
+        let group: Vec<usize> = (0..bindings.len()).collect();
+        for &index in &group {
+            let value = self.lower_let_rec_value(bindings, index, &group);
+            term = DbRef::new(DbTerm::App(term, value));
+        }
+        self.errors.truncate(errors);
+        term
+    }
+
+    fn lower_let_rec_value(
+        &mut self,
+        bindings: &[ast::Node<'src>],
+        index: usize,
+        open: &[usize],
+    ) -> DbRef {
+        let others: Vec<usize> = open.iter().copied().filter(|&i| i != index).collect();
+        let (name, value) = bindings[index].binding();
+        let hint = |index: usize| Hint(eval::Name::from(bindings[index].binding().0));
+        self.bound.push(name);
+        self.bound
+            .extend(others.iter().map(|&other| bindings[other].binding().0));
+        let mut value = self.lower(value);
+        self.bound.truncate(self.bound.len() - others.len());
+        for &other in others.iter().rev() {
+            value = DbRef::new(DbTerm::Abs(hint(other), value));
+        }
+        for &other in &others {
+            let other = self.lower_let_rec_value(bindings, other, &others);
+            value = DbRef::new(DbTerm::App(value, other));
+        }
+        self.bound.pop();
+        let value = DbRef::new(DbTerm::Abs(hint(index), value));
+        DbRef::new(DbTerm::App(DbRef::new(DbTerm::Fix), value))
+    }
+
+    fn lower_var(&mut self, name: &'src str, span: lexer::Span<'src>) -> DbTerm {
+        if let Some(index) = self.bound.iter().rev().position(|&bound| bound == name) {
+            return DbTerm::Bound(index);
+        }
+        if !(self.is_global)(name) {
+            self.errors.push(error::unbound_variable(span, name));
+        }
+        DbTerm::Global(eval::Name::from(name))
+    }
+}
+
+impl DbTerm {
+    // Lowers a runtime term. Its free variables are taken to be globals
+    pub fn from_term(term: &eval::Term) -> DbRef {
+        from_term_in(term, &mut Vec::new())
+    }
+
+    // Converts back to a named term. Binders keep their original names unless
+    // that would capture a variable, in which case they get a fresh one
+    pub fn to_term(&self) -> eval::TermRef {
+        self.to_term_in(&mut Vec::new())
+    }
+
+    fn to_term_in(&self, names: &mut Vec<eval::Name>) -> eval::TermRef {
+        let term = match self {
+            DbTerm::Bound(index) => eval::Term::Var(names[names.len() - 1 - index].clone()),
+            DbTerm::Global(name) => eval::Term::Var(name.clone()),
+            DbTerm::Num(num) => eval::Term::Num(*num),
+            DbTerm::Bool(value) => eval::Term::Bool(*value),
+            DbTerm::Fix => eval::Term::Fix,
+            DbTerm::Not(expr) => eval::Term::Not(expr.to_term_in(names)),
+            DbTerm::Binary(op, lhs, rhs) => {
+                eval::Term::Binary(*op, lhs.to_term_in(names), rhs.to_term_in(names))
+            }
+            DbTerm::App(func, arg) => {
+                eval::Term::App(func.to_term_in(names), arg.to_term_in(names))
+            }
+            DbTerm::Abs(Hint(hint), body) => {
+                let mut used = HashSet::new();
+                body.escaping_names(1, names, &mut used);
+                let name = if used.contains(hint) {
+                    eval::fresh_name(hint, |candidate| used.contains(candidate))
+                } else {
+                    hint.clone()
+                };
+                names.push(name.clone());
+                let body = body.to_term_in(names);
+                names.pop();
+                eval::Term::Abs(name, body)
+            }
+            DbTerm::If(condition, then, otherwise) => eval::Term::If(
+                condition.to_term_in(names),
+                then.to_term_in(names),
+                otherwise.to_term_in(names),
+            ),
+        };
+        eval::TermRef::new(term)
+    }
+
+    // Collects the names of the variables that refer outside of this term,
+    // `depth` being the number of binders crossed so far
+    fn escaping_names(&self, depth: usize, names: &[eval::Name], used: &mut HashSet<eval::Name>) {
+        match self {
+            DbTerm::Bound(index) if *index >= depth => {
+                used.insert(names[names.len() - 1 - (index - depth)].clone());
+            }
+            DbTerm::Global(name) => {
+                used.insert(name.clone());
+            }
+            DbTerm::Bound(_) | DbTerm::Num(_) | DbTerm::Bool(_) | DbTerm::Fix => {}
+            DbTerm::Not(expr) => expr.escaping_names(depth, names, used),
+            DbTerm::Binary(_, lhs, rhs) | DbTerm::App(lhs, rhs) => {
+                lhs.escaping_names(depth, names, used);
+                rhs.escaping_names(depth, names, used);
+            }
+            DbTerm::Abs(_, body) => body.escaping_names(depth + 1, names, used),
+            DbTerm::If(condition, then, otherwise) => {
+                condition.escaping_names(depth, names, used);
+                then.escaping_names(depth, names, used);
+                otherwise.escaping_names(depth, names, used);
+            }
+        }
+    }
+}
+
+fn from_term_in(term: &eval::Term, bound: &mut Vec<eval::Name>) -> DbRef {
+    let term = match term {
+        eval::Term::Var(name) => match bound.iter().rev().position(|bound| bound == name) {
+            Some(index) => DbTerm::Bound(index),
+            None => DbTerm::Global(name.clone()),
+        },
+        eval::Term::Num(num) => DbTerm::Num(*num),
+        eval::Term::Bool(value) => DbTerm::Bool(*value),
+        eval::Term::Fix => DbTerm::Fix,
+        eval::Term::Not(expr) => DbTerm::Not(from_term_in(expr, bound)),
+        eval::Term::Binary(op, lhs, rhs) => {
+            DbTerm::Binary(*op, from_term_in(lhs, bound), from_term_in(rhs, bound))
+        }
+        eval::Term::App(func, arg) => {
+            DbTerm::App(from_term_in(func, bound), from_term_in(arg, bound))
+        }
+        eval::Term::Abs(name, body) => {
+            bound.push(name.clone());
+            let body = from_term_in(body, bound);
+            bound.pop();
+            DbTerm::Abs(Hint(name.clone()), body)
+        }
+        eval::Term::If(condition, then, otherwise) => DbTerm::If(
+            from_term_in(condition, bound),
+            from_term_in(then, bound),
+            from_term_in(otherwise, bound),
+        ),
+        eval::Term::Thunk(cell) => return from_term_in(&cell.borrow(), bound),
+    };
+    DbRef::new(term)
+}
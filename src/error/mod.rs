@@ -1,63 +1,292 @@
-use super::*;
+mod json;
 
-pub struct Error<'src> {
-    message: String,
-    span: lexer::Span<'src>,
+use crate::{lexer, source};
+
+pub use json::Json;
+
+// How diagnostics are printed: for people on a terminal, or one JSON object per
+// line for tools
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Format {
+    #[default]
+    Human,
+    Json,
 }
 
-impl<'src> Error<'src> {
-    pub fn print(
-        &self,
-        f: &mut std::fmt::Formatter<'_>,
-        tokens: &lexer::TokenVec<'src>,
-    ) -> std::fmt::Result {
-        writeln!(f, "\x1B[1;31mError:\x1B[39m {}\x1B[m", self.message)?;
-        writeln!(
-            f,
-            "   \x1B[1;34m-->\x1B[m {}",
-            self.span.get_location_str(tokens.source)
-        )?;
-        writeln!(f, "    \x1B[1;34m|\x1B[m")?;
-        self.span.print(f, tokens)?;
-        writeln!(f)
+impl std::str::FromStr for Format {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "human" => Ok(Format::Human),
+            "json" => Ok(Format::Json),
+            _ => Err(format!(
+                "unknown error format `{s}`, expected `human` or `json`"
+            )),
+        }
     }
 }
 
-pub struct Errors<'src> {
-    tokens: lexer::TokenVec<'src>,
-    errors: Vec<Error<'src>>,
+// Diagnostics of one pass over a source, as returned by each of them
+pub trait Report: std::fmt::Display {
+    fn source(&self) -> &source::Source;
+
+    fn diagnostics(&self) -> &[Diagnostic<'_>];
+
+    fn emit(&self, format: Format) {
+        match format {
+            Format::Human => print!("{self}"),
+            Format::Json => {
+                for diagnostic in self.diagnostics() {
+                    println!("{}", diagnostic.to_json(self.source()));
+                }
+            }
+        }
+    }
 }
 
-impl<'src> Errors<'src> {
-    pub fn new(tokens: lexer::TokenVec<'src>) -> Self {
+// Fields of an error that is not about any place in particular, like a file
+// that cannot be read, in the same shape as those of a diagnostic
+pub fn message_fields(message: &str, file_name: Option<&str>) -> Vec<(&'static str, Json)> {
+    vec![
+        ("severity", Severity::Error.name().into()),
+        ("code", Json::Null),
+        ("message", message.into()),
+        ("file", file_name.into()),
+    ]
+}
+
+// Prints such an error in `format`
+pub fn emit_message(format: Format, message: &str, file_name: Option<&str>) {
+    match format {
+        Format::Human => println!("Error: {message}"),
+        Format::Json => println!("{}", Json::Object(message_fields(message, file_name))),
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+    Note,
+}
+
+impl Severity {
+    pub fn name(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+            Severity::Note => "note",
+        }
+    }
+
+    // Parameters of the SGR escape sequence it is printed with
+    fn color(self) -> &'static str {
+        match self {
+            Severity::Error => "1;31",
+            Severity::Warning => "1;33",
+            Severity::Note => "1;36",
+        }
+    }
+}
+
+// Part of the source a diagnostic points at, and what to say about it there
+#[derive(Debug)]
+pub struct Label<'src> {
+    pub span: lexer::Span<'src>,
+    pub message: String,
+}
+
+// A problem found in the source. The primary label shows where it is, and the
+// secondary ones other places involved, like where a parenthesis was opened
+#[derive(Debug)]
+pub struct Diagnostic<'src> {
+    pub severity: Severity,
+    // Stable identifier of the kind of problem, like `E0003`
+    pub code: &'static str,
+    pub message: String,
+    pub primary: Label<'src>,
+    pub secondary: Vec<Label<'src>>,
+    pub notes: Vec<String>,
+    pub help: Vec<String>,
+}
+
+impl<'src> Diagnostic<'src> {
+    pub fn new(
+        severity: Severity,
+        code: &'static str,
+        message: String,
+        span: lexer::Span<'src>,
+    ) -> Self {
         Self {
-            tokens,
-            errors: Vec::new(),
+            severity,
+            code,
+            message,
+            primary: Label {
+                span,
+                message: String::new(),
+            },
+            secondary: Vec::new(),
+            notes: Vec::new(),
+            help: Vec::new(),
         }
     }
 
-    pub fn invalid_number(&mut self, span: lexer::Span<'src>, details: &str) {
-        let literal = span.get_text(&self.tokens.source.text);
-        self.errors.push(Error {
+    pub fn error(code: &'static str, message: String, span: lexer::Span<'src>) -> Self {
+        Self::new(Severity::Error, code, message, span)
+    }
+
+    pub fn warning(code: &'static str, message: String, span: lexer::Span<'src>) -> Self {
+        Self::new(Severity::Warning, code, message, span)
+    }
+
+    pub fn with_label(mut self, message: impl Into<String>) -> Self {
+        self.primary.message = message.into();
+        self
+    }
+
+    pub fn with_secondary(mut self, span: lexer::Span<'src>, message: impl Into<String>) -> Self {
+        self.secondary.push(Label {
             span,
-            message: format!("invalid number literal `{literal}` ({details})"),
+            message: message.into(),
         });
+        self
     }
 
-    pub fn invalid_token(&mut self, span: lexer::Span<'src>) {
-        let literal = span.get_text(&self.tokens.source.text);
-        self.errors.push(Error {
-            span,
-            message: format!("invalid token `{literal}`"),
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn with_help(mut self, help: impl Into<String>) -> Self {
+        self.help.push(help.into());
+        self
+    }
+
+    // The message, with the file and the position of every label. Lines and
+    // columns start at 1, and columns count characters rather than bytes
+    pub fn to_json(&self, source: &source::Source) -> Json {
+        let mut fields = vec![
+            ("severity", self.severity.name().into()),
+            ("code", self.code.into()),
+            ("message", self.message.as_str().into()),
+            ("file", source.file_name.as_deref().into()),
+        ];
+        fields.extend(span_json(self.primary.span, source));
+        fields.push(("label", label_message(&self.primary).into()));
+        let secondary = self.secondary.iter().map(|label| {
+            let mut fields = vec![("message", label.message.as_str().into())];
+            fields.extend(span_json(label.span, source));
+            Json::Object(fields)
         });
+        fields.push(("secondary", Json::Array(secondary.collect())));
+        let texts =
+            |texts: &[String]| Json::Array(texts.iter().map(|text| text.as_str().into()).collect());
+        fields.push(("notes", texts(&self.notes)));
+        fields.push(("help", texts(&self.help)));
+        Json::Object(fields)
     }
-}
 
-impl std::fmt::Display for Errors<'_> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        for error in &self.errors {
-            error.print(f, &self.tokens)?;
+    pub fn print(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        tokens: &lexer::TokenVec,
+    ) -> std::fmt::Result {
+        let color = self.severity.color();
+        writeln!(
+            f,
+            "\x1B[{color}m{}[{}]:\x1B[39m {}\x1B[m",
+            self.severity.name(),
+            self.code,
+            self.message
+        )?;
+        writeln!(
+            f,
+            "   \x1B[1;34m-->\x1B[m {}",
+            self.primary.span.get_location_str(tokens.source)
+        )?;
+        writeln!(f, "    \x1B[1;34m|\x1B[m")?;
+
+        // Every line a label covers, with the underlines to draw below it. The
+        // message of a label goes on its last line
+        let source = tokens.source;
+        let mut lines: std::collections::BTreeMap<usize, Vec<Underline>> = Default::default();
+        let labels = std::iter::once((&self.primary, true))
+            .chain(self.secondary.iter().map(|label| (label, false)));
+        for (label, primary) in labels {
+            let span = label.span;
+            let first = source.get_line(span.start);
+            let last = source.get_line(span.end.saturating_sub(1).max(span.start));
+            for line in first..=last {
+                let (start, end) = span.columns(source, line);
+                lines.entry(line).or_default().push(Underline {
+                    start,
+                    end,
+                    primary,
+                    message: if line == last { &label.message } else { "" },
+                });
+            }
+        }
+
+        let mut prev_line = None;
+        for (&line, underlines) in &mut lines {
+            if prev_line.is_some_and(|prev| line > prev + 1) {
+                writeln!(f, "\x1B[1;34m...\x1B[m")?;
+            }
+            prev_line = Some(line);
+            self.primary.span.print_line(f, tokens, line)?;
+            underlines.sort_by_key(|underline| underline.start);
+            for underline in underlines.iter() {
+                let (mark, color) = if underline.primary {
+                    ('^', color)
+                } else {
+                    ('-', "1;34")
+                };
+                write!(
+                    f,
+                    "\x1B[1;34m    |\x1B[m {}\x1B[{color}m{}",
+                    " ".repeat(underline.start),
+                    mark.to_string().repeat(underline.end - underline.start),
+                )?;
+                if !underline.message.is_empty() {
+                    write!(f, " {}", underline.message)?;
+                }
+                writeln!(f, "\x1B[m")?;
+            }
+        }
+
+        for note in &self.notes {
+            let name = Severity::Note.name();
+            writeln!(f, "    \x1B[1;34m=\x1B[m \x1B[1m{name}:\x1B[m {note}")?;
+        }
+        for help in &self.help {
+            writeln!(f, "    \x1B[1;34m=\x1B[m \x1B[1mhelp:\x1B[m {help}")?;
         }
-        Ok(())
+        writeln!(f)
     }
 }
+
+fn label_message<'a>(label: &'a Label) -> Option<&'a str> {
+    Some(label.message.as_str()).filter(|message| !message.is_empty())
+}
+
+fn span_json(span: lexer::Span, source: &source::Source) -> Vec<(&'static str, Json)> {
+    let (line, column) = source.get_line_column(span.start);
+    let (end_line, end_column) = source.get_line_column(span.end);
+    vec![
+        ("start", span.start.into()),
+        ("end", span.end.into()),
+        ("line", (line + 1).into()),
+        ("column", (column + 1).into()),
+        ("end_line", (end_line + 1).into()),
+        ("end_column", (end_column + 1).into()),
+    ]
+}
+
+// Columns, in characters, of the part of a label on some line
+struct Underline<'a> {
+    start: usize,
+    end: usize,
+    primary: bool,
+    message: &'a str,
+}
@@ -0,0 +1,180 @@
+use super::*;
+
+// Church encodings of the built-in values and operators, written in the
+// language itself. Each definition may use the ones above it
+const PRELUDE: &str = "
+true   = λt.λf.t
+false  = λt.λf.f
+not    = λp.λt.λf.p f t
+and    = λp.λq.p q p
+or     = λp.λq.p p q
+iff    = λp.λq.p q (not q)
+xor    = λp.λq.p (not q) q
+zero   = λf.λx.x
+succ   = λn.λf.λx.f (n f x)
+add    = λm.λn.λf.λx.m f (n f x)
+mul    = λm.λn.λf.m (n f)
+pred   = λn.λf.λx.n (λg.λh.h (g f)) (λu.x) (λu.u)
+sub    = λm.λn.n pred m
+iszero = λn.n (λu.false) true
+leq    = λm.λn.iszero (sub m n)
+eq     = λm.λn.and (leq m n) (leq n m)
+neq    = λm.λn.not (eq m n)
+pair   = λa.λb.λs.s a b
+fst    = λp.p true
+snd    = λp.p false
+div    = λn.λm.snd (n (λp.pair (succ (fst p)) (leq (mul (succ (fst p)) m) n (succ (snd p)) (snd p))) (pair zero zero))
+";
+
+// What an expression is expected to produce, used to tell `0` from `false`
+// since both are encoded as `λt.λf.f`
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Kind {
+    Number,
+    Boolean,
+    Unknown,
+}
+
+impl Kind {
+    pub fn of(term: &Term) -> Self {
+        match term {
+            Term::Num(_) => Kind::Number,
+            Term::Bool(_) | Term::Not(_) => Kind::Boolean,
+            Term::Binary(BinOp::And | BinOp::Or | BinOp::Eq | BinOp::Neq, ..) => Kind::Boolean,
+            Term::Binary(..) => Kind::Number,
+            Term::If(_, then, otherwise) => match Kind::of(then) {
+                Kind::Unknown => Kind::of(otherwise),
+                kind => kind,
+            },
+            Term::Thunk(cell) => Kind::of(&cell.borrow()),
+            _ => Kind::Unknown,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Church {
+    combinators: std::collections::HashMap<Name, TermRef>,
+}
+
+impl Church {
+    pub fn new() -> Self {
+        let source = source::Source::from_string(PRELUDE.into());
+        let tokens = lexer::tokenise(&source).expect("invalid Church prelude");
+        let (ast, errors) = parser::parse_file(&tokens);
+        assert!(errors.is_empty(), "invalid Church prelude");
+        let ast::Ast::Source(definitions) = &*ast.ast else {
+            unreachable!("`parse_file` always returns a source");
+        };
+
+        let mut combinators: std::collections::HashMap<Name, TermRef> =
+            std::collections::HashMap::new();
+        for definition in definitions {
+            let ast::Ast::Assign(name, expr) = &*definition.ast else {
+                continue;
+            };
+            let term = combinators.iter().fold(
+                Term::from_ast(expr, &mut term::Origins::default()),
+                |term, (name, value)| Term::subst(&term, name, value),
+            );
+            combinators.insert(Name::from(*name), term);
+        }
+        Self { combinators }
+    }
+
+    pub fn combinator(&self, name: &str) -> TermRef {
+        self.combinators[name].clone()
+    }
+
+    pub fn numeral(num: u64) -> TermRef {
+        let f: Name = "f".into();
+        let x: Name = "x".into();
+        let body = (0..num).fold(TermRef::new(Term::Var(x.clone())), |body, _| {
+            TermRef::new(Term::App(TermRef::new(Term::Var(f.clone())), body))
+        });
+        TermRef::new(Term::Abs(f, TermRef::new(Term::Abs(x, body))))
+    }
+
+    // Replaces numbers, booleans and operators with their encodings
+    pub fn encode(&self, term: &TermRef) -> TermRef {
+        let apply = |func: TermRef, arg: TermRef| TermRef::new(Term::App(func, arg));
+        match term.as_ref() {
+            Term::Var(_) | Term::Fix => term.clone(),
+            Term::Num(num) => Church::numeral(*num),
+            Term::Bool(true) => self.combinator("true"),
+            Term::Bool(false) => self.combinator("false"),
+            Term::Not(expr) => apply(self.combinator("not"), self.encode(expr)),
+            // Division is only replaced once its divisor is known not to be
+            // zero, see `Reducer::step_church_div`
+            Term::Binary(BinOp::Div, lhs, rhs) => {
+                TermRef::new(Term::Binary(BinOp::Div, self.encode(lhs), self.encode(rhs)))
+            }
+            Term::Binary(op, lhs, rhs) => {
+                // `eq` and `neq` only compare numerals
+                let booleans = [lhs, rhs]
+                    .iter()
+                    .any(|operand| Kind::of(operand) == Kind::Boolean);
+                let name = match op {
+                    BinOp::Eq if booleans => "iff",
+                    BinOp::Neq if booleans => "xor",
+                    BinOp::Mul => "mul",
+                    BinOp::Div => "div",
+                    BinOp::Add => "add",
+                    BinOp::Sub => "sub",
+                    BinOp::And => "and",
+                    BinOp::Or => "or",
+                    BinOp::Eq => "eq",
+                    BinOp::Neq => "neq",
+                };
+                let func = apply(self.combinator(name), self.encode(lhs));
+                apply(func, self.encode(rhs))
+            }
+            Term::App(func, arg) => apply(self.encode(func), self.encode(arg)),
+            Term::Abs(name, body) => TermRef::new(Term::Abs(name.clone(), self.encode(body))),
+            // Kept as it is, so that only one branch gets reduced
+            Term::If(condition, then, otherwise) => TermRef::new(Term::If(
+                self.encode(condition),
+                self.encode(then),
+                self.encode(otherwise),
+            )),
+            Term::Thunk(cell) => self.encode(&cell.borrow()),
+        }
+    }
+
+    // Recognises a numeral or a boolean in a normal form, so that it can be
+    // printed as such. Anything else is left as it is
+    pub fn read_back(term: &TermRef, kind: Kind) -> TermRef {
+        let Term::Abs(a, body) = term.as_ref() else {
+            return term.clone();
+        };
+        let Term::Abs(b, body) = body.as_ref() else {
+            return term.clone();
+        };
+        if a == b {
+            return term.clone();
+        }
+        match body.as_ref() {
+            Term::Var(var) if var == a && kind != Kind::Number => {
+                return TermRef::new(Term::Bool(true));
+            }
+            Term::Var(var) if var == b && kind == Kind::Boolean => {
+                return TermRef::new(Term::Bool(false));
+            }
+            _ => {}
+        }
+
+        // Count the applications of `a` around `b`
+        let mut count = 0;
+        let mut body = body.clone();
+        loop {
+            match body.as_ref() {
+                Term::Var(var) if var == b => return TermRef::new(Term::Num(count)),
+                Term::App(func, arg) if matches!(func.as_ref(), Term::Var(var) if var == a) => {
+                    count += 1;
+                    body = arg.clone();
+                }
+                _ => return term.clone(),
+            }
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use super::*;
+
+use std::collections::{HashMap, HashSet};
+
+// Global definitions, i.e. every `Assign` seen so far. Later definitions of the
+// same name replace earlier ones. Globals are only looked up as they get
+// unfolded, so a definition may refer to itself or to globals that refer back
+// to it: such a group stands for its fixpoint, as if it were written with `fix`
+#[derive(Debug, Default)]
+pub struct Env {
+    definitions: HashMap<Name, TermRef>,
+    // Globals that refer back to themselves, worked out again when first
+    // needed after a definition changes
+    recursive: std::cell::OnceCell<HashSet<Name>>,
+}
+
+impl Env {
+    pub fn define(&mut self, name: Name, value: TermRef) {
+        self.definitions.insert(name, value);
+        self.recursive.take();
+    }
+
+    pub fn get(&self, name: &str) -> Option<&TermRef> {
+        self.definitions.get(name)
+    }
+
+    pub fn names(&self) -> impl Iterator<Item = &Name> {
+        self.definitions.keys()
+    }
+
+    pub fn is_recursive(&self, name: &str) -> bool {
+        self.recursive
+            .get_or_init(|| self.find_recursive())
+            .contains(name)
+    }
+
+    fn find_recursive(&self) -> HashSet<Name> {
+        let names: Vec<&Name> = self.definitions.keys().collect();
+        let index: HashMap<&Name, usize> = names
+            .iter()
+            .enumerate()
+            .map(|(i, &name)| (name, i))
+            .collect();
+        let edges: Vec<Vec<usize>> = names
+            .iter()
+            .map(|&name| {
+                let free = self.definitions[name].free_vars();
+                free.iter()
+                    .filter_map(|used| index.get(used).copied())
+                    .collect()
+            })
+            .collect();
+        graph::dependency_groups(&edges)
+            .into_iter()
+            .filter(|group| group.len() > 1 || edges[group[0]].contains(&group[0]))
+            .flatten()
+            .map(|i| names[i].clone())
+            .collect()
+    }
+}
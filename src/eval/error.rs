@@ -0,0 +1,158 @@
+use super::*;
+
+use crate::error::{Diagnostic, Report};
+
+#[derive(Debug)]
+pub struct EvalError {
+    // Stable code of the diagnostic it is reported as
+    code: &'static str,
+    message: String,
+    // How far evaluation got before it was stopped
+    term: Option<TermRef>,
+    // Terms the error was raised in, innermost first
+    context: Vec<TermRef>,
+}
+
+impl EvalError {
+    fn new(code: &'static str, message: String) -> Self {
+        Self {
+            code,
+            message,
+            term: None,
+            context: Vec::new(),
+        }
+    }
+
+    pub fn within(mut self, term: &TermRef) -> Self {
+        self.context.push(term.clone());
+        self
+    }
+
+    pub fn unbound_variable(name: &str) -> Self {
+        Self::new("E0005", format!("unbound variable `{name}`"))
+    }
+
+    pub fn type_mismatch(expected: &str, found: &Term) -> Self {
+        Self::new(
+            "E0006",
+            format!("expected {expected}, found {} `{found}`", found.type_name()),
+        )
+    }
+
+    pub fn not_a_function(found: &Term) -> Self {
+        Self::new(
+            "E0010",
+            format!("cannot apply {} `{found}`", found.type_name()),
+        )
+    }
+
+    pub fn division_by_zero() -> Self {
+        Self::new("E0012", "division by zero".into())
+    }
+
+    pub fn overflow(op: BinOp) -> Self {
+        Self::new("E0013", format!("arithmetic overflow in `{}`", op.symbol()))
+    }
+
+    pub fn step_limit(limit: usize, term: TermRef) -> Self {
+        Self::stopped(
+            "E0014",
+            format!("reduction step limit of {limit} reached"),
+            term,
+        )
+    }
+
+    pub fn timeout(timeout: std::time::Duration, term: TermRef) -> Self {
+        Self::stopped(
+            "E0015",
+            format!("evaluation timed out after {timeout:?}"),
+            term,
+        )
+    }
+
+    pub fn size_limit(limit: usize, term: TermRef) -> Self {
+        Self::stopped("E0016", format!("term grew beyond {limit} nodes"), term)
+    }
+
+    pub fn interrupted(term: TermRef) -> Self {
+        Self::stopped("E0017", "evaluation interrupted".into(), term)
+    }
+
+    pub fn diverges(term: TermRef) -> Self {
+        Self::stopped(
+            "E0018",
+            "term diverges: it reduces back to an earlier term".into(),
+            term,
+        )
+    }
+
+    fn stopped(code: &'static str, message: String, term: TermRef) -> Self {
+        Self {
+            term: Some(term),
+            ..Self::new(code, message)
+        }
+    }
+}
+
+const MAX_TERM_CHARS: usize = 200;
+
+pub type EvalResult<T> = Result<T, EvalError>;
+
+impl EvalError {
+    // Terms that hit a limit tend to be huge, only their start is shown
+    fn term_text(&self) -> Option<String> {
+        let term = self.term.as_ref()?.to_string();
+        match term.char_indices().nth(MAX_TERM_CHARS) {
+            Some((end, _)) => Some(format!("{} ...", &term[..end])),
+            None => Some(term),
+        }
+    }
+
+    // Reports the error at the innermost term it was raised in that comes from
+    // the source, or else at `span`, the expression whose evaluation failed
+    pub fn report<'src>(
+        &self,
+        tokens: &'src lexer::TokenVec<'src>,
+        origins: &Origins<'src>,
+        span: lexer::Span<'src>,
+    ) -> EvalDiagnosticVec<'src> {
+        let span = self
+            .context
+            .iter()
+            .find_map(|term| origins.get(term))
+            .unwrap_or(span);
+        let mut diagnostic = Diagnostic::error(self.code, self.message.clone(), span);
+        if let Some(term) = self.term_text() {
+            diagnostic = diagnostic.with_note(format!("partially reduced term: {term}"));
+        }
+        EvalDiagnosticVec {
+            tokens,
+            diagnostics: vec![diagnostic],
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct EvalDiagnosticVec<'src> {
+    pub tokens: &'src lexer::TokenVec<'src>,
+    pub diagnostics: Vec<Diagnostic<'src>>,
+}
+
+impl Report for EvalDiagnosticVec<'_> {
+    fn source(&self) -> &source::Source {
+        self.tokens.source
+    }
+
+    fn diagnostics(&self) -> &[Diagnostic<'_>] {
+        &self.diagnostics
+    }
+}
+
+impl std::fmt::Display for EvalDiagnosticVec<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for diagnostic in &self.diagnostics {
+            diagnostic.print(f, self.tokens)?;
+        }
+        Ok(())
+    }
+}
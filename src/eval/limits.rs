@@ -0,0 +1,23 @@
+use std::time::Duration;
+
+// Bounds on a single evaluation, so that a diverging term cannot hang the
+// interpreter. `None` disables a limit
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Limits {
+    // Number of reduction steps
+    pub max_steps: Option<usize>,
+    // Wall-clock time
+    pub timeout: Option<Duration>,
+    // Number of nodes in the term being reduced
+    pub max_size: Option<usize>,
+}
+
+impl Default for Limits {
+    fn default() -> Self {
+        Self {
+            max_steps: Some(1_000_000),
+            timeout: Some(Duration::from_secs(10)),
+            max_size: Some(10_000),
+        }
+    }
+}
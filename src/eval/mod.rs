@@ -0,0 +1,186 @@
+mod church;
+mod env;
+mod error;
+mod limits;
+mod reducer;
+mod reduction;
+mod strategy;
+mod subst;
+mod term;
+mod trace;
+
+use crate::{debruijn, graph, interrupt, lexer, parser, parser::ast, source, types};
+
+pub use church::Church;
+pub use env::Env;
+pub use error::{EvalDiagnosticVec, EvalError, EvalResult};
+pub use limits::Limits;
+pub use reduction::{Reduction, Rule};
+pub use strategy::Strategy;
+pub use subst::fresh_name;
+pub use term::{BinOp, Layout, Name, Origins, Term, TermRef};
+
+#[derive(Debug, Default)]
+pub struct Evaluator {
+    pub env: Env,
+    pub strategy: Strategy,
+    // Print every reduction step
+    pub trace: bool,
+    // Evaluate numbers, booleans and operators through their Church encodings
+    pub church: bool,
+    pub limits: Limits,
+    // Check types before loading or evaluating anything
+    pub typed: bool,
+    // Types of the globals loaded while checking them
+    pub types: types::Env,
+    church_encoding: std::cell::OnceCell<Church>,
+}
+
+impl Evaluator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // Binds the assignments in `ast` into the global environment. A bare
+    // expression is evaluated instead, and its value returned. Where the terms
+    // come from is recorded in `origins`
+    pub fn load<'src>(
+        &mut self,
+        tokens: &'src lexer::TokenVec<'src>,
+        ast: &ast::Node<'src>,
+        origins: &mut Origins<'src>,
+    ) -> Result<Option<TermRef>, EvalDiagnosticVec<'src>> {
+        match &*ast.ast {
+            ast::Ast::Source(asts) => {
+                for ast in asts {
+                    self.load(tokens, ast, origins)?;
+                }
+                Ok(None)
+            }
+            ast::Ast::Assign(name, expr) => {
+                self.env
+                    .define(Name::from(*name), Term::from_ast(expr, origins));
+                Ok(None)
+            }
+            ast::Ast::Signature(..) | ast::Ast::Comment(_) => Ok(None),
+            _ => self
+                .eval(&Term::from_ast(ast, origins))
+                .map(Some)
+                .map_err(|error| error.report(tokens, origins, ast.span)),
+        }
+    }
+
+    // Reduces `term` as far as the current strategy goes: to a value (a
+    // number, a boolean or an abstraction) for call-by-name and call-by-need,
+    // to full normal form for normal and applicative order
+    pub fn eval(&self, term: &TermRef) -> EvalResult<TermRef> {
+        self.eval_term(term).map_err(|error| error.within(term))
+    }
+
+    fn eval_term(&self, term: &TermRef) -> EvalResult<TermRef> {
+        let church = self
+            .church
+            .then(|| self.church_encoding.get_or_init(Church::new));
+        let reducer = reducer::Reducer {
+            env: &self.env,
+            strategy: self.strategy,
+            church,
+        };
+        let mut progress = Progress {
+            steps: 0,
+            started: std::time::Instant::now(),
+        };
+        let Some(church) = church else {
+            return self.run(&reducer, term.clone(), &mut progress);
+        };
+
+        let kind = church::Kind::of(term);
+        let mut value = self.run(&reducer, church.encode(term), &mut progress)?;
+        // Numerals and booleans are only recognised in normal form, so weak
+        // strategies are finished off with normal order
+        if !self.strategy.reduces_under_abstractions() {
+            let reducer = reducer::Reducer {
+                strategy: Strategy::NormalOrder,
+                ..reducer
+            };
+            value = self.run(&reducer, value, &mut progress)?;
+        }
+        Ok(Church::read_back(&value, kind))
+    }
+
+    fn run(
+        &self,
+        reducer: &reducer::Reducer,
+        mut term: TermRef,
+        progress: &mut Progress,
+    ) -> EvalResult<TermRef> {
+        // Cycles are looked for with Brent's algorithm: the term is compared
+        // against a snapshot that is retaken after 1, 2, 4, 8, ... steps, so
+        // any cycle is eventually caught without keeping every term around.
+        // Thunks are updated in place, so the snapshot must not share them
+        let mut size = term.size();
+        let mut snapshot = (size, debruijn::DbTerm::from_term(&term).to_term());
+        let mut since_snapshot = 0;
+        let mut period = 1;
+        loop {
+            self.check_limits(&term, size, progress)?;
+            // Call-by-need updates thunks in place, so the term must be
+            // printed before the step is taken
+            let layout = self.trace.then(|| Layout::new(&term));
+            let Some(reduction) = reducer.step(&term, &mut Vec::new())? else {
+                break;
+            };
+            progress.steps += 1;
+            if let Some(layout) = &layout {
+                let step = trace::TraceStep {
+                    number: progress.steps,
+                    layout,
+                    reduction: &reduction,
+                };
+                println!("{step}");
+            }
+            term = reduction.term;
+            size = term.size();
+
+            // Comparing sizes first keeps growing terms cheap
+            if size == snapshot.0 && term.alpha_eq(&snapshot.1) {
+                return Err(EvalError::diverges(Term::view(&term)));
+            }
+            since_snapshot += 1;
+            if since_snapshot == period {
+                snapshot = (size, debruijn::DbTerm::from_term(&term).to_term());
+                since_snapshot = 0;
+                period *= 2;
+            }
+        }
+        Ok(Term::view(&term))
+    }
+
+    fn check_limits(&self, term: &TermRef, size: usize, progress: &Progress) -> EvalResult<()> {
+        if interrupt::take() {
+            return Err(EvalError::interrupted(Term::view(term)));
+        }
+        let Limits {
+            max_steps,
+            timeout,
+            max_size,
+        } = self.limits;
+        if let Some(max_steps) = max_steps.filter(|&max| progress.steps >= max) {
+            return Err(EvalError::step_limit(max_steps, Term::view(term)));
+        }
+        if let Some(timeout) = timeout.filter(|&max| progress.started.elapsed() >= max) {
+            return Err(EvalError::timeout(timeout, Term::view(term)));
+        }
+        if let Some(max_size) = max_size.filter(|&max| size > max) {
+            return Err(EvalError::size_limit(max_size, Term::view(term)));
+        }
+        Ok(())
+    }
+}
+
+// Work done so far by one call to `Evaluator::eval`, which may span several
+// runs of the reducer
+struct Progress {
+    steps: usize,
+    started: std::time::Instant,
+}
@@ -0,0 +1,340 @@
+use super::*;
+
+// Reduction machinery, set up for one evaluation
+pub struct Reducer<'a> {
+    pub env: &'a Env,
+    pub strategy: Strategy,
+    // Encodings applied to globals as they get unfolded, in Church mode
+    pub church: Option<&'a church::Church>,
+}
+
+impl Reducer<'_> {
+    // Performs a single reduction step, or returns `None` if there is nothing
+    // left to reduce. `bound` holds the names of the enclosing abstractions,
+    // which are never looked up in the global environment
+    pub fn step(&self, term: &TermRef, bound: &mut Vec<Name>) -> EvalResult<Option<Reduction>> {
+        // Errors remember every term they were raised in, to be reported at
+        // the innermost one that comes from the source
+        self.step_term(term, bound)
+            .map_err(|error| error.within(term))
+    }
+
+    fn step_term(&self, term: &TermRef, bound: &mut Vec<Name>) -> EvalResult<Option<Reduction>> {
+        match term.as_ref() {
+            Term::Num(_) | Term::Bool(_) | Term::Fix => Ok(None),
+            Term::Var(name) if bound.contains(name) => Ok(None),
+            // Left for `step_app` to unfold once applied to a value
+            Term::Var(name) if self.delays_recursion() && self.env.is_recursive(name) => Ok(None),
+            Term::Var(name) => self.unfold(name, term).map(Some),
+            Term::Abs(..) if !self.strategy.reduces_under_abstractions() => Ok(None),
+            Term::Abs(name, body) => {
+                bound.push(name.clone());
+                let body = self.step(body, bound);
+                bound.pop();
+                Ok(body?.map(|reduction| {
+                    reduction.map(|body| TermRef::new(Term::Abs(name.clone(), body)))
+                }))
+            }
+            Term::App(func, arg) => self.step_app(term, func, arg, bound),
+            Term::Not(expr) => {
+                if let Some(reduction) = self.step(expr, bound)? {
+                    return Ok(Some(reduction.map(|expr| TermRef::new(Term::Not(expr)))));
+                }
+                match Term::view(expr).as_ref() {
+                    Term::Bool(value) => Ok(Some(Reduction::new(
+                        TermRef::new(Term::Bool(!value)),
+                        Rule::Arithmetic,
+                        term,
+                    ))),
+                    Term::Num(_) | Term::Abs(..) | Term::Fix => {
+                        Err(EvalError::type_mismatch("boolean", &Term::view(expr)).within(expr))
+                    }
+                    _ => Ok(None),
+                }
+            }
+            Term::Binary(op, lhs, rhs) => self.step_binary(term, *op, lhs, rhs, bound),
+            Term::If(condition, then, otherwise) => {
+                self.step_if(term, condition, then, otherwise, bound)
+            }
+            // Reducing a thunk updates it for every other occurrence as well
+            Term::Thunk(cell) => {
+                let inner = cell.borrow().clone();
+                match self.step(&inner, bound)? {
+                    Some(reduction) => {
+                        *cell.borrow_mut() = reduction.term.clone();
+                        Ok(Some(reduction.map(|_| term.clone())))
+                    }
+                    None => Ok(None),
+                }
+            }
+        }
+    }
+
+    // Replaces the global `name` with its definition
+    fn unfold(&self, name: &str, term: &TermRef) -> EvalResult<Reduction> {
+        match self.env.get(name) {
+            Some(value) => {
+                let value = match self.church {
+                    Some(church) => church.encode(value),
+                    None => value.clone(),
+                };
+                Ok(Reduction::new(value, Rule::Delta, term))
+            }
+            None => Err(EvalError::unbound_variable(name)),
+        }
+    }
+
+    // Whether recursion waits to be applied to a value before it is unfolded,
+    // as it would through the Z combinator. Strict strategies would otherwise
+    // unfold it forever, when reducing its arguments or under abstractions
+    fn delays_recursion(&self) -> bool {
+        self.strategy.is_strict()
+    }
+
+    fn step_app(
+        &self,
+        term: &TermRef,
+        func: &TermRef,
+        arg: &TermRef,
+        bound: &mut Vec<Name>,
+    ) -> EvalResult<Option<Reduction>> {
+        let with_func = |reduction: Reduction| {
+            Some(reduction.map(|func| TermRef::new(Term::App(func, arg.clone()))))
+        };
+        let with_arg = |reduction: Reduction| {
+            Some(reduction.map(|arg| TermRef::new(Term::App(func.clone(), arg))))
+        };
+
+        // Applicative order contracts a redex only once both sides are normal
+        if self.strategy == Strategy::ApplicativeOrder {
+            if let Some(reduction) = self.step(func, bound)? {
+                return Ok(with_func(reduction));
+            }
+            if let Some(reduction) = self.step(arg, bound)? {
+                return Ok(with_arg(reduction));
+            }
+        }
+
+        match Term::view(func).as_ref() {
+            Term::Abs(name, body) => {
+                let result = Term::subst(body, name, &self.share(arg));
+                return Ok(Some(Reduction::new(result, Rule::Beta, term)));
+            }
+            Term::Fix if !self.delays_recursion() => {
+                let unfolded = TermRef::new(Term::App(arg.clone(), term.clone()));
+                return Ok(Some(Reduction::new(unfolded, Rule::Fix, term)));
+            }
+            Term::Num(_) | Term::Bool(_) => {
+                return Err(EvalError::not_a_function(&Term::view(func)).within(func))
+            }
+            _ => {}
+        }
+        if self.delays_recursion() && is_value(arg, bound) {
+            match Term::view(func).as_ref() {
+                Term::Var(name) if !bound.contains(name) => {
+                    return Ok(with_func(self.unfold(name, func)?));
+                }
+                Term::App(fix, f) if matches!(Term::view(fix).as_ref(), Term::Fix) => {
+                    let unfolded = TermRef::new(Term::App(f.clone(), func.clone()));
+                    return Ok(with_func(Reduction::new(unfolded, Rule::Fix, func)));
+                }
+                _ => {}
+            }
+        }
+        if let Some(reduction) = self.step(func, bound)? {
+            return Ok(with_func(reduction));
+        }
+        // The head is stuck on a bound variable, so move on to the argument
+        if self.strategy == Strategy::NormalOrder {
+            if let Some(reduction) = self.step(arg, bound)? {
+                return Ok(with_arg(reduction));
+            }
+        }
+        Ok(None)
+    }
+
+    // Wraps an argument about to be substituted so that, under call-by-need,
+    // its occurrences share a single reduction
+    fn share(&self, arg: &TermRef) -> TermRef {
+        match arg.as_ref() {
+            _ if self.strategy != Strategy::CallByNeed => arg.clone(),
+            Term::Var(_) | Term::Num(_) | Term::Bool(_) | Term::Abs(..) | Term::Thunk(_) => {
+                arg.clone()
+            }
+            _ => TermRef::new(Term::Thunk(std::cell::RefCell::new(arg.clone()))),
+        }
+    }
+
+    fn step_if(
+        &self,
+        term: &TermRef,
+        condition: &TermRef,
+        then: &TermRef,
+        otherwise: &TermRef,
+        bound: &mut Vec<Name>,
+    ) -> EvalResult<Option<Reduction>> {
+        let with_condition =
+            |condition| TermRef::new(Term::If(condition, then.clone(), otherwise.clone()));
+        if let Some(reduction) = self.step(condition, bound)? {
+            return Ok(Some(reduction.map(with_condition)));
+        }
+        match Term::view(condition).as_ref() {
+            Term::Bool(value) => {
+                let branch = if *value { then } else { otherwise };
+                Ok(Some(Reduction::new(branch.clone(), Rule::If, term)))
+            }
+            // A Church boolean picks one of the built-in booleans instead
+            Term::Abs(..) if self.church.is_some() => {
+                let choose = |value| TermRef::new(Term::Bool(value));
+                let applied = TermRef::new(Term::App(condition.clone(), choose(true)));
+                let applied = TermRef::new(Term::App(applied, choose(false)));
+                Ok(Some(
+                    Reduction::new(applied, Rule::If, condition).map(with_condition),
+                ))
+            }
+            Term::Num(_) | Term::Abs(..) | Term::Fix => {
+                Err(EvalError::type_mismatch("boolean", &Term::view(condition)).within(condition))
+            }
+            // The condition is stuck on a bound variable, so neither branch is
+            // taken
+            _ => Ok(None),
+        }
+    }
+
+    fn step_binary(
+        &self,
+        term: &TermRef,
+        op: BinOp,
+        lhs: &TermRef,
+        rhs: &TermRef,
+        bound: &mut Vec<Name>,
+    ) -> EvalResult<Option<Reduction>> {
+        if let (BinOp::Div, Some(church)) = (op, self.church) {
+            return self.step_church_div(church, term, lhs, rhs, bound);
+        }
+        // Operands are reduced left to right, `&&` and `||` short-circuit
+        if let Some(reduction) = self.step(lhs, bound)? {
+            return Ok(Some(
+                reduction.map(|lhs| TermRef::new(Term::Binary(op, lhs, rhs.clone()))),
+            ));
+        }
+        let lhs_value = Term::view(lhs);
+        match (op, lhs_value.as_ref()) {
+            (BinOp::And, Term::Bool(false)) | (BinOp::Or, Term::Bool(true)) => {
+                return Ok(Some(Reduction::new(lhs_value, Rule::Arithmetic, term)))
+            }
+            _ => check_operand(op, lhs)?,
+        }
+        if let Some(reduction) = self.step(rhs, bound)? {
+            return Ok(Some(
+                reduction.map(|rhs| TermRef::new(Term::Binary(op, lhs.clone(), rhs))),
+            ));
+        }
+        let rhs_value = Term::view(rhs);
+        check_operand(op, rhs)?;
+
+        let value = match (op, lhs_value.as_ref(), rhs_value.as_ref()) {
+            (BinOp::And | BinOp::Or, Term::Bool(_), Term::Bool(_)) => {
+                return Ok(Some(Reduction::new(rhs_value, Rule::Arithmetic, term)))
+            }
+            (BinOp::Eq, Term::Num(a), Term::Num(b)) => Term::Bool(a == b),
+            (BinOp::Eq, Term::Bool(a), Term::Bool(b)) => Term::Bool(a == b),
+            (BinOp::Neq, Term::Num(a), Term::Num(b)) => Term::Bool(a != b),
+            (BinOp::Neq, Term::Bool(a), Term::Bool(b)) => Term::Bool(a != b),
+            (BinOp::Eq | BinOp::Neq, Term::Num(_), Term::Bool(_)) => {
+                return Err(EvalError::type_mismatch("number", &rhs_value).within(rhs))
+            }
+            (BinOp::Eq | BinOp::Neq, Term::Bool(_), Term::Num(_)) => {
+                return Err(EvalError::type_mismatch("boolean", &rhs_value).within(rhs))
+            }
+            (_, &Term::Num(a), &Term::Num(b)) => Term::Num(arithmetic(op, a, b)?),
+            // One of the operands is stuck on a bound variable
+            _ => return Ok(None),
+        };
+        Ok(Some(Reduction::new(
+            TermRef::new(value),
+            Rule::Arithmetic,
+            term,
+        )))
+    }
+}
+
+impl Reducer<'_> {
+    // Division of Church numerals, which would give back the dividend when
+    // dividing by zero. The divisor is brought to normal form first, whatever
+    // the strategy, so that zero can be told apart
+    fn step_church_div(
+        &self,
+        church: &church::Church,
+        term: &TermRef,
+        lhs: &TermRef,
+        rhs: &TermRef,
+        bound: &mut Vec<Name>,
+    ) -> EvalResult<Option<Reduction>> {
+        let normal = Reducer {
+            strategy: Strategy::NormalOrder,
+            ..*self
+        };
+        if let Some(reduction) = normal.step(rhs, bound)? {
+            return Ok(Some(reduction.map(|rhs| {
+                TermRef::new(Term::Binary(BinOp::Div, lhs.clone(), rhs))
+            })));
+        }
+        let divisor = church::Church::read_back(&Term::view(rhs), church::Kind::Number);
+        if let Term::Num(0) = divisor.as_ref() {
+            return Err(EvalError::division_by_zero());
+        }
+        let div = TermRef::new(Term::App(church.combinator("div"), lhs.clone()));
+        Ok(Some(Reduction::new(
+            TermRef::new(Term::App(div, rhs.clone())),
+            Rule::Arithmetic,
+            term,
+        )))
+    }
+}
+
+// Checks an operand that cannot be reduced any further. Operands stuck on a
+// bound variable are accepted, as nothing is known about them yet
+fn check_operand(op: BinOp, operand: &TermRef) -> EvalResult<()> {
+    let expected = match op {
+        BinOp::And | BinOp::Or => "boolean",
+        BinOp::Eq | BinOp::Neq => "number or boolean",
+        _ => "number",
+    };
+    let value = Term::view(operand);
+    let valid = match value.as_ref() {
+        Term::Num(_) => !matches!(op, BinOp::And | BinOp::Or),
+        Term::Bool(_) => matches!(op, BinOp::And | BinOp::Or | BinOp::Eq | BinOp::Neq),
+        Term::Abs(..) | Term::Fix => false,
+        _ => true,
+    };
+    if valid {
+        Ok(())
+    } else {
+        Err(EvalError::type_mismatch(expected, &value).within(operand))
+    }
+}
+
+// Whether `term` is a value rather than stuck on a bound variable
+fn is_value(term: &TermRef, bound: &[Name]) -> bool {
+    match Term::view(term).as_ref() {
+        Term::Num(_) | Term::Bool(_) | Term::Fix | Term::Abs(..) => true,
+        Term::Var(name) => !bound.contains(name),
+        // `fix f` waiting for its argument
+        Term::App(func, _) => matches!(Term::view(func).as_ref(), Term::Fix),
+        _ => false,
+    }
+}
+
+fn arithmetic(op: BinOp, a: u64, b: u64) -> EvalResult<u64> {
+    let result = match op {
+        BinOp::Add => a.checked_add(b),
+        // Numbers are naturals, so subtraction stops at zero
+        BinOp::Sub => Some(a.saturating_sub(b)),
+        BinOp::Mul => a.checked_mul(b),
+        BinOp::Div if b == 0 => return Err(EvalError::division_by_zero()),
+        BinOp::Div => Some(a / b),
+        _ => unreachable!("`{}` is not an arithmetic operator", op.symbol()),
+    };
+    result.ok_or_else(|| EvalError::overflow(op))
+}
@@ -0,0 +1,55 @@
+use super::*;
+
+// Rule behind a reduction step
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Rule {
+    // `(λx.body) arg` becomes `body` with `arg` in place of `x`
+    Beta,
+    // A global is replaced by its definition
+    Delta,
+    // `fix f` becomes `f (fix f)`
+    Fix,
+    // An `if` becomes the branch its condition picks
+    If,
+    // A built-in operator is applied to its values
+    Arithmetic,
+}
+
+impl std::fmt::Display for Rule {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Rule::Beta => f.pad("beta"),
+            Rule::Delta => f.pad("delta"),
+            Rule::Fix => f.pad("fix"),
+            Rule::If => f.pad("if"),
+            Rule::Arithmetic => f.pad("arithmetic"),
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Reduction {
+    // The whole term after the step
+    pub term: TermRef,
+    pub rule: Rule,
+    // The subterm that was contracted, as found in the term before the step
+    pub redex: TermRef,
+}
+
+impl Reduction {
+    pub fn new(term: TermRef, rule: Rule, redex: &TermRef) -> Self {
+        Self {
+            term,
+            rule,
+            redex: redex.clone(),
+        }
+    }
+
+    // Puts the reduced subterm back into its enclosing term
+    pub fn map(self, rebuild: impl FnOnce(TermRef) -> TermRef) -> Self {
+        Self {
+            term: rebuild(self.term),
+            ..self
+        }
+    }
+}
@@ -0,0 +1,64 @@
+// Order in which redexes are contracted
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Strategy {
+    // Leftmost-outermost redex first, down to full normal form
+    NormalOrder,
+    // Leftmost-innermost redex first: arguments are normalised before the
+    // call, down to full normal form
+    ApplicativeOrder,
+    // Arguments are passed unevaluated, down to weak head normal form
+    #[default]
+    CallByName,
+    // Like call-by-name, but each argument is reduced at most once and the
+    // result shared by all of its occurrences
+    CallByNeed,
+}
+
+impl Strategy {
+    pub const ALL: [Strategy; 4] = [
+        Strategy::NormalOrder,
+        Strategy::ApplicativeOrder,
+        Strategy::CallByName,
+        Strategy::CallByNeed,
+    ];
+
+    // Whether reduction continues inside the body of abstractions
+    pub fn reduces_under_abstractions(self) -> bool {
+        matches!(self, Strategy::NormalOrder | Strategy::ApplicativeOrder)
+    }
+
+    // Whether arguments are reduced before the call
+    pub fn is_strict(self) -> bool {
+        self == Strategy::ApplicativeOrder
+    }
+}
+
+impl std::str::FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "normal" | "normal-order" => Ok(Strategy::NormalOrder),
+            "applicative" | "applicative-order" => Ok(Strategy::ApplicativeOrder),
+            "name" | "call-by-name" => Ok(Strategy::CallByName),
+            "need" | "call-by-need" => Ok(Strategy::CallByNeed),
+            _ => Err(format!(
+                "unknown strategy `{name}` (expected one of {})",
+                Strategy::ALL
+                    .map(|strategy| format!("`{strategy}`"))
+                    .join(", ")
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for Strategy {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Strategy::NormalOrder => write!(f, "normal-order"),
+            Strategy::ApplicativeOrder => write!(f, "applicative-order"),
+            Strategy::CallByName => write!(f, "call-by-name"),
+            Strategy::CallByNeed => write!(f, "call-by-need"),
+        }
+    }
+}
@@ -0,0 +1,121 @@
+use super::*;
+
+use std::collections::HashSet;
+
+impl Term {
+    // Replaces the free occurrences of `name` inside `term` with `value`.
+    // Abstractions that would capture a free variable of `value` are renamed
+    // first, e.g. substituting `y` for `x` in `λy.x` gives `λy₁.y`
+    pub fn subst(term: &TermRef, name: &str, value: &TermRef) -> TermRef {
+        let free = value.free_vars();
+        subst_avoiding(term, name, value, &free)
+    }
+
+    pub fn free_vars(&self) -> HashSet<Name> {
+        let mut free = HashSet::new();
+        collect_free_vars(self, &mut Vec::new(), &mut free);
+        free
+    }
+
+    pub fn occurs_free(&self, name: &str) -> bool {
+        match self {
+            Term::Var(var) => var.as_ref() == name,
+            Term::Num(_) | Term::Bool(_) | Term::Fix => false,
+            Term::Not(expr) => expr.occurs_free(name),
+            Term::Binary(_, lhs, rhs) | Term::App(lhs, rhs) => {
+                lhs.occurs_free(name) || rhs.occurs_free(name)
+            }
+            Term::Abs(arg, body) => arg.as_ref() != name && body.occurs_free(name),
+            Term::If(condition, then, otherwise) => {
+                condition.occurs_free(name) || then.occurs_free(name) || otherwise.occurs_free(name)
+            }
+            Term::Thunk(cell) => cell.borrow().occurs_free(name),
+        }
+    }
+
+    // Whether both terms are equal up to the names of bound variables
+    pub fn alpha_eq(&self, other: &Term) -> bool {
+        debruijn::DbTerm::from_term(self) == debruijn::DbTerm::from_term(other)
+    }
+}
+
+// Returns a variant of `name` for which `is_taken` is false, numbered with
+// subscripts so that it is still a valid identifier: `x₁`, `x₂`, ...
+pub fn fresh_name(name: &str, is_taken: impl Fn(&str) -> bool) -> Name {
+    let base = name.trim_end_matches(|c| ('₀'..='₉').contains(&c));
+    (1..)
+        .map(|index: u64| {
+            let subscript: String = index
+                .to_string()
+                .chars()
+                .map(|digit| char::from_u32('₀' as u32 + digit as u32 - '0' as u32).unwrap())
+                .collect();
+            format!("{base}{subscript}")
+        })
+        .find(|candidate| !is_taken(candidate))
+        .map(Name::from)
+        .unwrap()
+}
+
+fn subst_avoiding(term: &TermRef, name: &str, value: &TermRef, free: &HashSet<Name>) -> TermRef {
+    match term.as_ref() {
+        Term::Var(var) if var.as_ref() == name => value.clone(),
+        Term::Var(_) | Term::Num(_) | Term::Bool(_) | Term::Fix => term.clone(),
+        Term::Not(expr) => TermRef::new(Term::Not(subst_avoiding(expr, name, value, free))),
+        Term::Binary(op, lhs, rhs) => TermRef::new(Term::Binary(
+            *op,
+            subst_avoiding(lhs, name, value, free),
+            subst_avoiding(rhs, name, value, free),
+        )),
+        Term::App(func, arg) => TermRef::new(Term::App(
+            subst_avoiding(func, name, value, free),
+            subst_avoiding(arg, name, value, free),
+        )),
+        Term::If(condition, then, otherwise) => TermRef::new(Term::If(
+            subst_avoiding(condition, name, value, free),
+            subst_avoiding(then, name, value, free),
+            subst_avoiding(otherwise, name, value, free),
+        )),
+        // Shadowed: `name` is not free inside the body
+        Term::Abs(arg, _) if arg.as_ref() == name => term.clone(),
+        // The binder would capture a free variable of `value`, so rename it
+        Term::Abs(arg, body) if free.contains(arg) && body.occurs_free(name) => {
+            let fresh = fresh_name(arg, |candidate| {
+                candidate == name || free.contains(candidate) || body.occurs_free(candidate)
+            });
+            let body = Term::subst(body, arg, &TermRef::new(Term::Var(fresh.clone())));
+            TermRef::new(Term::Abs(fresh, subst_avoiding(&body, name, value, free)))
+        }
+        Term::Abs(arg, body) => TermRef::new(Term::Abs(
+            arg.clone(),
+            subst_avoiding(body, name, value, free),
+        )),
+        // Thunks only ever hold closed arguments
+        Term::Thunk(_) => term.clone(),
+    }
+}
+
+fn collect_free_vars(term: &Term, bound: &mut Vec<Name>, free: &mut HashSet<Name>) {
+    match term {
+        Term::Var(name) if !bound.contains(name) => {
+            free.insert(name.clone());
+        }
+        Term::Var(_) | Term::Num(_) | Term::Bool(_) | Term::Fix => {}
+        Term::Not(expr) => collect_free_vars(expr, bound, free),
+        Term::Binary(_, lhs, rhs) | Term::App(lhs, rhs) => {
+            collect_free_vars(lhs, bound, free);
+            collect_free_vars(rhs, bound, free);
+        }
+        Term::If(condition, then, otherwise) => {
+            collect_free_vars(condition, bound, free);
+            collect_free_vars(then, bound, free);
+            collect_free_vars(otherwise, bound, free);
+        }
+        Term::Abs(arg, body) => {
+            bound.push(arg.clone());
+            collect_free_vars(body, bound, free);
+            bound.pop();
+        }
+        Term::Thunk(cell) => collect_free_vars(&cell.borrow(), bound, free),
+    }
+}
@@ -0,0 +1,327 @@
+use super::*;
+
+use parser::Precedence;
+
+pub type Name = std::rc::Rc<str>;
+pub type TermRef = std::rc::Rc<Term>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum BinOp {
+    Mul,
+    Div,
+    Add,
+    Sub,
+    And,
+    Or,
+    Eq,
+    Neq,
+}
+
+impl BinOp {
+    pub fn symbol(self) -> &'static str {
+        match self {
+            BinOp::Mul => "*",
+            BinOp::Div => "/",
+            BinOp::Add => "+",
+            BinOp::Sub => "-",
+            BinOp::And => "&&",
+            BinOp::Or => "||",
+            BinOp::Eq => "==",
+            BinOp::Neq => "!=",
+        }
+    }
+
+    fn precedence(self) -> Precedence {
+        match self {
+            BinOp::Eq | BinOp::Neq => Precedence::Comparison,
+            BinOp::Or => Precedence::Disjunction,
+            BinOp::And => Precedence::Conjunction,
+            BinOp::Add | BinOp::Sub => Precedence::Sum,
+            BinOp::Mul | BinOp::Div => Precedence::Product,
+        }
+    }
+}
+
+// Spans of the source nodes that terms were lowered from, so that evaluation
+// errors can point back at them. Terms are keyed by address and kept alive so
+// that the address is not reused by another term
+#[derive(Debug, Default)]
+pub struct Origins<'src> {
+    spans: std::collections::HashMap<*const Term, (TermRef, lexer::Span<'src>)>,
+}
+
+impl<'src> Origins<'src> {
+    fn insert(&mut self, term: &TermRef, span: lexer::Span<'src>) {
+        self.spans
+            .insert(TermRef::as_ptr(term), (term.clone(), span));
+    }
+
+    pub fn get(&self, term: &TermRef) -> Option<lexer::Span<'src>> {
+        self.spans
+            .get(&TermRef::as_ptr(term))
+            .map(|(_, span)| *span)
+    }
+}
+
+// Runtime representation of an expression. Unlike `ast::Ast`, names are owned
+// so terms can outlive the source they were parsed from (e.g. across REPL lines)
+#[derive(Debug)]
+pub enum Term {
+    Var(Name),
+    Num(u64),
+    Bool(bool),
+    // The fixpoint primitive, unfolded by the reducer
+    Fix,
+    Not(TermRef),
+    Binary(BinOp, TermRef, TermRef),
+    App(TermRef, TermRef),
+    Abs(Name, TermRef),
+    // Only the branch picked by the condition is ever reduced
+    If(TermRef, TermRef, TermRef),
+    // Argument shared by all of its occurrences under call-by-need. It is
+    // updated in place as it gets reduced
+    Thunk(std::cell::RefCell<TermRef>),
+}
+
+impl Term {
+    // Lowers a parsed expression, recording where each of its nodes comes from
+    // in `origins`. Assignments and whole files are handled by
+    // `Evaluator::load`, so they never reach this point
+    pub fn from_ast<'src>(ast: &ast::Node<'src>, origins: &mut Origins<'src>) -> TermRef {
+        let mut binary = |op, lhs, rhs| {
+            TermRef::new(Term::Binary(
+                op,
+                Term::from_ast(lhs, origins),
+                Term::from_ast(rhs, origins),
+            ))
+        };
+        let term = match &*ast.ast {
+            ast::Ast::Var(name) => TermRef::new(Term::Var(Name::from(*name))),
+            ast::Ast::Num(num) => TermRef::new(Term::Num(*num)),
+            ast::Ast::Fix => TermRef::new(Term::Fix),
+            ast::Ast::Not(expr) => TermRef::new(Term::Not(Term::from_ast(expr, origins))),
+            ast::Ast::App(func, arg) => TermRef::new(Term::App(
+                Term::from_ast(func, origins),
+                Term::from_ast(arg, origins),
+            )),
+            ast::Ast::Mul(lhs, rhs) => binary(BinOp::Mul, lhs, rhs),
+            ast::Ast::Div(lhs, rhs) => binary(BinOp::Div, lhs, rhs),
+            ast::Ast::Add(lhs, rhs) => binary(BinOp::Add, lhs, rhs),
+            ast::Ast::Sub(lhs, rhs) => binary(BinOp::Sub, lhs, rhs),
+            ast::Ast::And(lhs, rhs) => binary(BinOp::And, lhs, rhs),
+            ast::Ast::Or(lhs, rhs) => binary(BinOp::Or, lhs, rhs),
+            ast::Ast::Eq(lhs, rhs) => binary(BinOp::Eq, lhs, rhs),
+            ast::Ast::Neq(lhs, rhs) => binary(BinOp::Neq, lhs, rhs),
+            ast::Ast::Abs(arg, _, body) => {
+                TermRef::new(Term::Abs(Name::from(*arg), Term::from_ast(body, origins)))
+            }
+            ast::Ast::If(condition, then, otherwise) => TermRef::new(Term::If(
+                Term::from_ast(condition, origins),
+                Term::from_ast(then, origins),
+                Term::from_ast(otherwise, origins),
+            )),
+            // `let x = e1 in e2` is `(λx. e2) e1`, one binding after the other
+            ast::Ast::Let(bindings, body) | ast::Ast::Where(body, bindings) => bindings
+                .iter()
+                .rev()
+                .fold(Term::from_ast(body, origins), |body, binding| {
+                    let (name, value) = binding.binding();
+                    let abs = TermRef::new(Term::Abs(Name::from(name), body));
+                    TermRef::new(Term::App(abs, Term::from_ast(value, origins)))
+                }),
+            // `let rec f = e1 in e2` is `(λf. e2) (fix (λf. e1))`, and a
+            // group of bindings `(λf. λg. e) vf vg`, see `let_rec_value`
+            ast::Ast::LetRec(bindings, body) => {
+                let group: Vec<usize> = (0..bindings.len()).collect();
+                let mut term = Term::from_ast(body, origins);
+                for binding in bindings.iter().rev() {
+                    term = TermRef::new(Term::Abs(Name::from(binding.binding().0), term));
+                }
+                for &index in &group {
+                    let value = Term::let_rec_value(bindings, index, &group, origins);
+                    term = TermRef::new(Term::App(term, value));
+                }
+                term
+            }
+            ast::Ast::Assign(..)
+            | ast::Ast::Signature(..)
+            | ast::Ast::Comment(_)
+            | ast::Ast::Source(_) => {
+                unreachable!("assignments are not expressions")
+            }
+            ast::Ast::Error => unreachable!("trees with syntax errors are not evaluated"),
+        };
+        origins.insert(&term, ast.span);
+        term
+    }
+
+    // Value of the binding `index` of a `let rec`, built as Bekič does for
+    // mutual recursion: `fix (λf. e)`, where each other binding still `open`
+    // is bound in `e` to its own value, built in the same way with `f` no
+    // longer open. So `let rec f = ef; g = eg` gives `f` the value
+    // `fix (λf. (λg. ef) (fix (λg. eg)))`
+    fn let_rec_value<'src>(
+        bindings: &[ast::Node<'src>],
+        index: usize,
+        open: &[usize],
+        origins: &mut Origins<'src>,
+    ) -> TermRef {
+        let others: Vec<usize> = open.iter().copied().filter(|&i| i != index).collect();
+        let (name, value) = bindings[index].binding();
+        let mut value = Term::from_ast(value, origins);
+        for &other in others.iter().rev() {
+            value = TermRef::new(Term::Abs(Name::from(bindings[other].binding().0), value));
+        }
+        for &other in &others {
+            let other = Term::let_rec_value(bindings, other, &others, origins);
+            value = TermRef::new(Term::App(value, other));
+        }
+        let value = TermRef::new(Term::Abs(Name::from(name), value));
+        TermRef::new(Term::App(TermRef::new(Term::Fix), value))
+    }
+
+    // Looks through thunks to the term they currently hold
+    pub fn view(term: &TermRef) -> TermRef {
+        let mut term = term.clone();
+        while let Term::Thunk(cell) = term.as_ref() {
+            let inner = cell.borrow().clone();
+            term = inner;
+        }
+        term
+    }
+
+    pub fn type_name(&self) -> &'static str {
+        match self {
+            Term::Num(_) => "number",
+            Term::Bool(_) => "boolean",
+            Term::Abs(..) | Term::Fix => "function",
+            Term::Thunk(cell) => cell.borrow().type_name(),
+            _ => "expression",
+        }
+    }
+
+    // Number of nodes in the term as printed, looking through thunks
+    pub fn size(&self) -> usize {
+        match self {
+            Term::Var(_) | Term::Num(_) | Term::Bool(_) | Term::Fix => 1,
+            Term::Not(expr) | Term::Abs(_, expr) => 1 + expr.size(),
+            Term::Binary(_, lhs, rhs) | Term::App(lhs, rhs) => 1 + lhs.size() + rhs.size(),
+            Term::If(condition, then, otherwise) => {
+                1 + condition.size() + then.size() + otherwise.size()
+            }
+            Term::Thunk(cell) => cell.borrow().size(),
+        }
+    }
+
+    fn print(&self, printer: &mut Printer, prec: Precedence) {
+        if let Term::Thunk(cell) = self {
+            return cell.borrow().print(printer, prec);
+        }
+        let start = printer.text.len();
+        let own = self.precedence();
+        if own < prec {
+            printer.text.push('(');
+        }
+        match self {
+            Term::Var(name) => printer.text.push_str(name),
+            Term::Num(num) => printer.text.push_str(&num.to_string()),
+            Term::Bool(value) => printer.text.push_str(&value.to_string()),
+            Term::Fix => printer.text.push_str("fix"),
+            Term::Not(expr) => {
+                printer.text.push('!');
+                expr.print(printer, Precedence::Unary);
+            }
+            Term::Binary(op, lhs, rhs) => {
+                // All binary operators are left-associative
+                lhs.print(printer, own);
+                printer.text.push_str(&format!(" {} ", op.symbol()));
+                rhs.print(printer, own.next());
+            }
+            Term::App(func, arg) => {
+                func.print(printer, Precedence::Application);
+                printer.text.push(' ');
+                arg.print(printer, Precedence::Unary);
+            }
+            Term::Abs(arg, body) => {
+                printer.text.push_str(&format!("λ{arg}."));
+                body.print(printer, Precedence::Abstraction);
+            }
+            Term::If(condition, then, otherwise) => {
+                printer.text.push_str("if ");
+                condition.print(printer, Precedence::Abstraction);
+                printer.text.push_str(" then ");
+                then.print(printer, Precedence::Abstraction);
+                printer.text.push_str(" else ");
+                otherwise.print(printer, Precedence::Abstraction);
+            }
+            Term::Thunk(_) => unreachable!(),
+        }
+        if own < prec {
+            printer.text.push(')');
+        }
+        if let Some(spans) = &mut printer.spans {
+            spans.push((self as *const Term as usize, start, printer.text.len()));
+        }
+    }
+
+    fn precedence(&self) -> Precedence {
+        match self {
+            Term::Var(_) | Term::Num(_) | Term::Bool(_) | Term::Fix | Term::Not(_) => {
+                Precedence::Unary
+            }
+            Term::Binary(op, ..) => op.precedence(),
+            Term::App(..) => Precedence::Application,
+            Term::Abs(..) | Term::If(..) => Precedence::Abstraction,
+            Term::Thunk(cell) => cell.borrow().precedence(),
+        }
+    }
+}
+
+impl std::fmt::Display for Term {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut printer = Printer {
+            text: String::new(),
+            spans: None,
+        };
+        self.print(&mut printer, Precedence::Abstraction);
+        write!(f, "{}", printer.text)
+    }
+}
+
+struct Printer {
+    text: String,
+    // Address and byte range of every subterm printed
+    spans: Option<Vec<(usize, usize, usize)>>,
+}
+
+// Printed form of a term that remembers where each subterm ended up, so that
+// one of them can be pointed at later on
+pub struct Layout {
+    pub text: String,
+    spans: Vec<(usize, usize, usize)>,
+}
+
+impl Layout {
+    pub fn new(term: &Term) -> Self {
+        let mut printer = Printer {
+            text: String::new(),
+            spans: Some(Vec::new()),
+        };
+        term.print(&mut printer, Precedence::Abstraction);
+        Self {
+            text: printer.text,
+            spans: printer.spans.unwrap_or_default(),
+        }
+    }
+
+    // Byte range of the leftmost occurrence of `subterm`. Substitution may
+    // share a subterm between several places, so it is identified by address
+    pub fn find(&self, subterm: &Term) -> Option<(usize, usize)> {
+        let address = subterm as *const Term as usize;
+        self.spans
+            .iter()
+            .filter(|&&(other, ..)| other == address)
+            .map(|&(_, start, end)| (start, end))
+            .min()
+    }
+}
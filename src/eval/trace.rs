@@ -0,0 +1,26 @@
+use super::*;
+
+// One line of the trace: the term before a step, with its redex highlighted
+pub struct TraceStep<'a> {
+    pub number: usize,
+    pub layout: &'a Layout,
+    pub reduction: &'a Reduction,
+}
+
+impl std::fmt::Display for TraceStep<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "\x1B[1;34m{:4} |\x1B[m {:<10} ",
+            self.number, self.reduction.rule
+        )?;
+
+        // Printed terms are valid source, so they get the same coloring
+        let source = source::Source::from_string(self.layout.text.clone());
+        let (start, end) = self.layout.find(&self.reduction.redex).unwrap_or((0, 0));
+        match lexer::tokenise(&source) {
+            Ok(tokens) => lexer::Span::new(&source.text, start, end).print_inline(f, &tokens),
+            Err(_) => write!(f, "{}", self.layout.text),
+        }
+    }
+}
@@ -0,0 +1,42 @@
+use super::*;
+
+use crate::error::{Diagnostic, Report};
+use crate::source;
+
+pub fn moved_comment<'src>(
+    span: lexer::Span<'src>,
+    definition: lexer::Span<'src>,
+) -> Diagnostic<'src> {
+    Diagnostic::warning(
+        "W0004",
+        "comment moved out of its definition".to_string(),
+        span,
+    )
+    .with_secondary(definition, "placed before this definition")
+    .with_note("comments inside a definition are kept on the lines before it")
+}
+
+#[derive(Debug)]
+pub struct FormatDiagnosticVec<'src> {
+    pub tokens: &'src lexer::TokenVec<'src>,
+    pub diagnostics: Vec<Diagnostic<'src>>,
+}
+
+impl Report for FormatDiagnosticVec<'_> {
+    fn source(&self) -> &source::Source {
+        self.tokens.source
+    }
+
+    fn diagnostics(&self) -> &[Diagnostic<'_>] {
+        &self.diagnostics
+    }
+}
+
+impl std::fmt::Display for FormatDiagnosticVec<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for diagnostic in &self.diagnostics {
+            diagnostic.print(f, self.tokens)?;
+        }
+        Ok(())
+    }
+}
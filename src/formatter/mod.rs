@@ -0,0 +1,232 @@
+mod error;
+
+use crate::{lexer, parser, parser::ast};
+
+pub use error::FormatDiagnosticVec;
+
+// Definitions longer than this are broken over several lines
+const MAX_WIDTH: usize = 80;
+
+// Lays out a parsed file canonically. Expressions are printed with the usual
+// spacing and as few parentheses as possible, the `=` of consecutive one-line
+// definitions are aligned, and blank lines between paragraphs are kept. The
+// comments that had to be moved are reported
+pub fn format<'src>(
+    tokens: &'src lexer::TokenVec<'src>,
+    ast: &ast::Node<'src>,
+    style: parser::Style,
+) -> (String, FormatDiagnosticVec<'src>) {
+    let ast::Ast::Source(items) = &*ast.ast else {
+        unreachable!("`parse_file` always returns a source");
+    };
+    let source = tokens.source;
+
+    let mut lines = Vec::new();
+    let mut diagnostics = Vec::new();
+    // Last line of the source taken by the previous item
+    let mut prev_line = None;
+    for (i, item) in items.iter().enumerate() {
+        // Comments from inside a definition come just before it, and are
+        // placed as if they were where the definition starts
+        let owner = items[i..]
+            .iter()
+            .find(|owner| !matches!(*owner.ast, ast::Ast::Comment(_)))
+            .filter(|owner| owner.outer.start <= item.outer.start);
+        if let (ast::Ast::Comment(_), Some(owner)) = (&*item.ast, owner) {
+            diagnostics.push(error::moved_comment(item.span, owner.outer));
+        }
+        let first_line = source.get_line(owner.unwrap_or(item).outer.start);
+        let last_line = source.get_line(item.outer.end.saturating_sub(1));
+        match &*item.ast {
+            // A comment after a definition on the same line stays there
+            ast::Ast::Comment(text) if prev_line == Some(first_line) => {
+                if let Some(
+                    Line::Definition { comment, .. }
+                    | Line::Block { comment, .. }
+                    | Line::Bindings { comment, .. }
+                    | Line::Signature { comment, .. },
+                ) = lines.last_mut()
+                {
+                    *comment = Some(text.trim_end());
+                    continue;
+                }
+                lines.push(Line::Comment(text.trim_end()));
+            }
+            _ if prev_line.is_some_and(|prev| first_line > prev + 1) => {
+                lines.push(Line::Blank);
+                lines.push(Line::new(tokens, item, style));
+            }
+            _ => lines.push(Line::new(tokens, item, style)),
+        }
+        prev_line = Some(last_line);
+    }
+
+    let mut text = String::new();
+    for (i, line) in lines.iter().enumerate() {
+        match line {
+            Line::Definition {
+                name,
+                expr,
+                comment,
+            } => {
+                let width = alignment(&lines, i);
+                text.push_str(&format!(
+                    "{name}{} = {expr}",
+                    " ".repeat(width - name.chars().count())
+                ));
+                push_comment(&mut text, *comment);
+            }
+            Line::Block {
+                name,
+                expr,
+                comment,
+            } => {
+                text.push_str(&format!("{name} = (\n{expr}\n)"));
+                push_comment(&mut text, *comment);
+            }
+            Line::Bindings {
+                definition,
+                comment,
+            }
+            | Line::Signature {
+                signature: definition,
+                comment,
+            } => {
+                text.push_str(definition);
+                push_comment(&mut text, *comment);
+            }
+            Line::Comment(comment) => text.push_str(comment),
+            Line::Blank => {}
+        }
+        text.push('\n');
+    }
+    let diagnostics = FormatDiagnosticVec {
+        tokens,
+        diagnostics,
+    };
+    (text, diagnostics)
+}
+
+enum Line<'a> {
+    // A definition that fits on one line
+    Definition {
+        name: &'a str,
+        expr: String,
+        comment: Option<&'a str>,
+    },
+    // A definition whose body gets lines of its own, inside parentheses, and
+    // is broken further if it is still too long
+    Block {
+        name: &'a str,
+        expr: String,
+        comment: Option<&'a str>,
+    },
+    // A definition with a `where` or a `let` whose bindings take lines of
+    // their own, or with a `where` too long for one line, printed whole as
+    // they are laid out
+    Bindings {
+        definition: String,
+        comment: Option<&'a str>,
+    },
+    Signature {
+        signature: String,
+        comment: Option<&'a str>,
+    },
+    Comment(&'a str),
+    Blank,
+}
+
+impl<'a> Line<'a> {
+    fn new(tokens: &lexer::TokenVec, item: &ast::Node<'a>, style: parser::Style) -> Self {
+        match &*item.ast {
+            ast::Ast::Assign(name, expr) => {
+                let printed = expr.pretty(style).to_string();
+                let too_long = name.chars().count() + printed.chars().count() + 3 > MAX_WIDTH;
+                // A `where` cannot go inside parentheses
+                let is_where = matches!(*expr.ast, ast::Ast::Where(..));
+                if printed.contains('\n') || is_where && too_long {
+                    return Line::Bindings {
+                        definition: item.pretty(style).to_string(),
+                        comment: None,
+                    };
+                }
+                if !is_where && (too_long || is_multiline_block(tokens, expr)) {
+                    Line::Block {
+                        name,
+                        expr: expr.pretty_within(style, 1, MAX_WIDTH),
+                        comment: None,
+                    }
+                } else {
+                    Line::Definition {
+                        name,
+                        expr: printed,
+                        comment: None,
+                    }
+                }
+            }
+            ast::Ast::Signature(..) => Line::Signature {
+                signature: item.pretty(style).to_string(),
+                comment: None,
+            },
+            ast::Ast::Comment(text) => Line::Comment(text.trim_end()),
+            _ => unreachable!("a source only holds definitions, signatures and comments"),
+        }
+    }
+}
+
+// Whether `expr` was written as a parenthesised body over several lines, which
+// is then kept as a block even if it would fit on one line
+fn is_multiline_block(tokens: &lexer::TokenVec, expr: &ast::Node) -> bool {
+    let source = tokens.source;
+    if source.get_line(expr.outer.start) == source.get_line(expr.outer.end.saturating_sub(1)) {
+        return false;
+    }
+    // The `(` at the start must be closed by the `)` at the end
+    let mut depth = 0usize;
+    let inside = tokens
+        .tokens
+        .iter()
+        .skip_while(|token| token.span.start < expr.outer.start)
+        .take_while(|token| token.span.end <= expr.outer.end);
+    for token in inside {
+        match token.token_type {
+            lexer::TokenType::LPar => depth += 1,
+            lexer::TokenType::RPar => depth = depth.saturating_sub(1),
+            // Anything outside of the first parentheses
+            _ if depth == 0 => return false,
+            _ => {}
+        }
+        if depth == 0 {
+            return token.span.end == expr.outer.end;
+        }
+    }
+    false
+}
+
+// Width of the names in the run of one-line definitions around line `i`. Runs
+// are broken by blank lines and blocks, but not by signatures or comments
+fn alignment(lines: &[Line], i: usize) -> usize {
+    let in_run = |line: &&Line| {
+        matches!(
+            line,
+            Line::Definition { .. } | Line::Signature { .. } | Line::Comment(_)
+        )
+    };
+    let before = lines[..i].iter().rev().take_while(in_run);
+    let after = lines[i..].iter().take_while(in_run);
+    before
+        .chain(after)
+        .filter_map(|line| match line {
+            Line::Definition { name, .. } => Some(name.chars().count()),
+            _ => None,
+        })
+        .max()
+        .unwrap_or(0)
+}
+
+fn push_comment(text: &mut String, comment: Option<&str>) {
+    if let Some(comment) = comment {
+        text.push(' ');
+        text.push_str(comment);
+    }
+}
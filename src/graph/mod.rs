@@ -0,0 +1,71 @@
+// Splits definitions into groups that refer to each other, directly or not,
+// with every group after the ones it refers to. `edges[i]` lists the
+// definitions the `i`th one refers to. This is Tarjan's algorithm
+pub fn dependency_groups(edges: &[Vec<usize>]) -> Vec<Vec<usize>> {
+    let mut search = Search {
+        edges,
+        index: vec![None; edges.len()],
+        low: vec![0; edges.len()],
+        stack: Vec::new(),
+        on_stack: vec![false; edges.len()],
+        next: 0,
+        groups: Vec::new(),
+    };
+    for node in 0..edges.len() {
+        if search.index[node].is_none() {
+            search.visit(node);
+        }
+    }
+    search.groups
+}
+
+struct Search<'a> {
+    edges: &'a [Vec<usize>],
+    // Order in which each node was reached
+    index: Vec<Option<usize>>,
+    // Earliest node on the stack reachable from each one
+    low: Vec<usize>,
+    stack: Vec<usize>,
+    on_stack: Vec<bool>,
+    next: usize,
+    groups: Vec<Vec<usize>>,
+}
+
+impl Search<'_> {
+    fn visit(&mut self, node: usize) {
+        self.index[node] = Some(self.next);
+        self.low[node] = self.next;
+        self.next += 1;
+        self.stack.push(node);
+        self.on_stack[node] = true;
+
+        let edges = self.edges;
+        for &next in &edges[node] {
+            match self.index[next] {
+                None => {
+                    self.visit(next);
+                    self.low[node] = self.low[node].min(self.low[next]);
+                }
+                Some(index) if self.on_stack[next] => {
+                    self.low[node] = self.low[node].min(index);
+                }
+                Some(_) => {}
+            }
+        }
+
+        // The node is the first of its group reached, the rest is above it
+        if Some(self.low[node]) == self.index[node] {
+            let mut group = Vec::new();
+            loop {
+                let member = self.stack.pop().expect("node is on the stack");
+                self.on_stack[member] = false;
+                group.push(member);
+                if member == node {
+                    break;
+                }
+            }
+            group.reverse();
+            self.groups.push(group);
+        }
+    }
+}
@@ -0,0 +1,38 @@
+use std::ffi::c_int;
+use std::sync::atomic::{AtomicBool, Ordering};
+
+// Set by the SIGINT handler, cleared once the interruption has been handled
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+
+const SIGINT: c_int = 2;
+const SIG_ERR: usize = usize::MAX;
+
+extern "C" {
+    // glibc gives `signal` BSD semantics: the handler stays installed and
+    // interrupted system calls are restarted
+    fn signal(signum: c_int, handler: extern "C" fn(c_int)) -> usize;
+}
+
+extern "C" fn on_interrupt(_signum: c_int) {
+    // Only async-signal-safe work is allowed here, an atomic store is
+    INTERRUPTED.store(true, Ordering::Relaxed);
+}
+
+// Makes Ctrl-C request an interruption instead of ending the process
+pub fn install() -> std::io::Result<()> {
+    // Safety: the handler only stores to an atomic
+    if unsafe { signal(SIGINT, on_interrupt) } == SIG_ERR {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+// Whether Ctrl-C was pressed since the last call
+pub fn take() -> bool {
+    INTERRUPTED.swap(false, Ordering::Relaxed)
+}
+
+// Forgets a Ctrl-C pressed while nothing was running
+pub fn clear() {
+    INTERRUPTED.store(false, Ordering::Relaxed);
+}
@@ -1,39 +1,20 @@
 use super::*;
 
-#[derive(Debug)]
-pub struct LexerError<'src> {
-    span: Span<'src>,
-    message: String,
-}
-
-impl<'src> LexerError<'src> {
-    pub fn invalid_number(source: &'src source::Source, span: Span<'src>, details: &str) -> Self {
-        let literal = span.get_text(&source.text);
-        Self {
-            span,
-            message: format!("invalid number literal `{literal}` ({details})"),
-        }
-    }
+use crate::error::{Diagnostic, Report};
 
-    pub fn invalid_token(source: &'src source::Source, span: Span<'src>) -> Self {
-        let literal = span.get_text(&source.text);
-        Self {
-            span,
-            message: format!("invalid token `{literal}`"),
-        }
-    }
+pub fn invalid_token<'src>(source: &'src source::Source, span: Span<'src>) -> Diagnostic<'src> {
+    let literal = span.get_text(&source.text);
+    Diagnostic::error("E0001", format!("invalid token `{literal}`"), span)
+}
 
-    pub fn print(&self, f: &mut std::fmt::Formatter<'_>, tokens: &TokenVec) -> std::fmt::Result {
-        writeln!(f, "\x1B[1;31mLexer error:\x1B[39m {}\x1B[m", self.message)?;
-        writeln!(
-            f,
-            "   \x1B[1;34m-->\x1B[m {}",
-            self.span.get_location_str(tokens.source)
-        )?;
-        writeln!(f, "    \x1B[1;34m|\x1B[m")?;
-        self.span.print(f, tokens)?;
-        writeln!(f)
-    }
+pub fn invalid_number<'src>(
+    source: &'src source::Source,
+    span: Span<'src>,
+    details: &str,
+) -> Diagnostic<'src> {
+    let literal = span.get_text(&source.text);
+    Diagnostic::error("E0002", format!("invalid number literal `{literal}`"), span)
+        .with_note(details)
 }
 
 pub type LexerResult<'src, T> = Result<T, LexerErrorVec<'src>>;
@@ -41,7 +22,17 @@ pub type LexerResult<'src, T> = Result<T, LexerErrorVec<'src>>;
 #[derive(Debug)]
 pub struct LexerErrorVec<'src> {
     pub tokens: TokenVec<'src>,
-    pub errors: Vec<LexerError<'src>>,
+    pub errors: Vec<Diagnostic<'src>>,
+}
+
+impl Report for LexerErrorVec<'_> {
+    fn source(&self) -> &source::Source {
+        self.tokens.source
+    }
+
+    fn diagnostics(&self) -> &[Diagnostic<'_>] {
+        &self.errors
+    }
 }
 
 impl std::fmt::Display for LexerErrorVec<'_> {
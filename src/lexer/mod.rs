@@ -8,11 +8,10 @@ use crate::source;
 use error::*;
 use token_vec::*;
 
-pub use error::LexerError;
-pub use span::Span;
+pub use span::{token_color, Span};
 pub use token::{Token, TokenType};
 pub use token_vec::{TokenVec, TokenIter};
 
-pub fn tokenise(source: &source::Source) -> LexerResult<TokenVec> {
+pub fn tokenise(source: &source::Source) -> LexerResult<'_, TokenVec<'_>> {
     TokenStream::new(source).tokenise()
 }
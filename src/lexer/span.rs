@@ -22,6 +22,21 @@ impl<'src> Span<'src> {
         }
     }
 
+    // Span of `slice`, which must be part of `source`
+    pub fn from_slice(source: &'src str, slice: &'src str) -> Self {
+        let start = slice.as_ptr() as usize - source.as_ptr() as usize;
+        Self::new(source, start, start + slice.len())
+    }
+
+    // Span covering from the start of `self` to the end of `other`
+    pub fn join(self, other: Self) -> Self {
+        Self {
+            start: self.start,
+            end: other.end,
+            source: self.source,
+        }
+    }
+
     pub fn get_text(&self, source: &'src str) -> &'src str {
         &source[self.start..self.end]
     }
@@ -38,24 +53,48 @@ impl<'src> Span<'src> {
         }
     }
 
-    pub fn print<'t>(
+    // Prints line `line` of the source with its number, and the part of the
+    // span on it in red
+    pub fn print_line(
         &self,
         f: &mut std::fmt::Formatter<'_>,
-        tokens: &'t TokenVec<'t>,
+        tokens: &TokenVec<'_>,
+        line: usize,
     ) -> std::fmt::Result {
+        write!(f, "\x1B[1;34m{:3} |\x1B[m ", line + 1)?;
+        print_colored(f, tokens, line, self.start, self.end)?;
+        writeln!(f, "\x1B[m")
+    }
 
-        let line_start = tokens.source.get_line(self.start);
-        let line_end = tokens.source.get_line(self.end);
-
-        for i in line_start..=line_end {
-            print_line(f, tokens, i, self.start, self.end)?;
-        }
+    // Where the part of the span on line `line` starts and ends, in characters.
+    // An empty span, like the end of the input, still takes one column
+    pub fn columns(&self, source: &source::Source, line: usize) -> (usize, usize) {
+        let (line_start, line_end) = source.get_line_offset(line);
+        let text = source.text[line_start..line_end].trim_end();
+        let column = |offset: usize| {
+            let offset = offset.clamp(line_start, line_start + text.len());
+            source.text[line_start..offset].chars().count()
+        };
+        let start = column(self.start);
+        (start, column(self.end).max(start + 1))
+    }
 
-        Ok(())
+    // Prints the line holding the span colored like `print_line` does, but
+    // without the line number
+    pub fn print_inline(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        tokens: &TokenVec<'_>,
+    ) -> std::fmt::Result {
+        let line = tokens.source.get_line(self.start);
+        print_colored(f, tokens, line, self.start, self.end)?;
+        write!(f, "\x1B[m")
     }
 }
 
-fn print_line(
+// Prints a line with its tokens colored by type, and the range between `start`
+// and `end` in red
+fn print_colored(
     f: &mut std::fmt::Formatter<'_>,
     tokens: &TokenVec<'_>,
     line: usize,
@@ -64,8 +103,6 @@ fn print_line(
 ) -> std::fmt::Result {
     let (start_offset, end_offset) = tokens.source.get_line_offset(line);
 
-    write!(f, "\x1B[1;34m{:3} |\x1B[m ", line + 1)?;
-
     let mut curr_token = tokens
         .tokens
         .binary_search_by(|t| {
@@ -82,12 +119,7 @@ fn print_line(
     let mut curr_color = "";
     let slice = &tokens.source.text[start_offset..end_offset].trim_end();
 
-    let slice_len = slice.chars().count();
-
-    let mut start_chars = slice_len;
-    let mut end_chars = slice_len + 1;
-
-    for (i_chars, (i, c)) in slice.char_indices().enumerate() {
+    for (i, c) in slice.char_indices() {
         let i = i + start_offset;
         let mut new_color = "";
         if let Some(token) = tokens.tokens.get(curr_token) {
@@ -99,25 +131,7 @@ fn print_line(
             let span_start = tokens.tokens[curr_token].span.start;
             let span_end = tokens.tokens[curr_token].span.end;
             if i >= span_start && i < span_end {
-                new_color = match tokens.tokens[curr_token].token_type {
-                    TokenType::Ident(_) => ";38;5;153",
-                    TokenType::Num(_) => ";38;5;133",
-                    TokenType::Mul
-                    | TokenType::Div
-                    | TokenType::Add
-                    | TokenType::Sub
-                    | TokenType::And
-                    | TokenType::Or
-                    | TokenType::Not
-                    | TokenType::Eq
-                    | TokenType::Neq
-                    | TokenType::Assign
-                    | TokenType::LPar
-                    | TokenType::RPar
-                    | TokenType::Eol => ";38;5;133",
-                    TokenType::Lambda | TokenType::Dot => ";1;38;5;215",
-                    TokenType::Comment(_) => ";38;5;244",
-                };
+                new_color = token_color(&tokens.tokens[curr_token].token_type);
             }
         }
         if i >= start && i < end {
@@ -128,20 +142,41 @@ fn print_line(
             curr_color = new_color;
         }
         write!(f, "{}", c)?;
-
-        if i == start {
-            start_chars = i_chars;
-        }
-        if i == end {
-            end_chars = i_chars;
-        }
     }
 
-    writeln!(f, "\x1B[m")?;
-    writeln!(
-        f,
-        "\x1B[1;34m    |\x1B[m {}\x1B[1;31m{}\x1B[m",
-        " ".repeat(start_chars),
-        "^".repeat(usize::saturating_sub(end_chars, start_chars)),
-    )
+    Ok(())
+}
+
+// Color of a token, as the parameters of an SGR escape sequence. Shared by
+// error messages, traces and the REPL line editor
+pub fn token_color(token_type: &TokenType) -> &'static str {
+    match token_type {
+        TokenType::Ident(_) => ";38;5;153",
+        TokenType::Num(_) => ";38;5;133",
+        TokenType::Mul
+        | TokenType::Div
+        | TokenType::Add
+        | TokenType::Sub
+        | TokenType::And
+        | TokenType::Or
+        | TokenType::Not
+        | TokenType::Eq
+        | TokenType::Neq
+        | TokenType::Assign
+        | TokenType::Colon
+        | TokenType::LPar
+        | TokenType::RPar
+        | TokenType::Eol => ";38;5;133",
+        TokenType::Lambda
+        | TokenType::Dot
+        | TokenType::Let
+        | TokenType::Rec
+        | TokenType::In
+        | TokenType::Where
+        | TokenType::Fix
+        | TokenType::If
+        | TokenType::Then
+        | TokenType::Else => ";1;38;5;215",
+        TokenType::Comment(_) => ";38;5;244",
+    }
 }
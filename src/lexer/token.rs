@@ -16,6 +16,15 @@ pub enum TokenType<'src> {
     Assign,
     Lambda,
     Dot,
+    Colon,
+    Let,
+    Rec,
+    In,
+    Where,
+    Fix,
+    If,
+    Then,
+    Else,
     LPar,
     RPar,
     Comment(&'src str),
@@ -39,6 +48,15 @@ impl std::fmt::Display for TokenType<'_> {
             TokenType::Assign => write!(f, "`=`"),
             TokenType::Lambda => write!(f, "`\\`"),
             TokenType::Dot => write!(f, "`.`"),
+            TokenType::Colon => write!(f, "`:`"),
+            TokenType::Let => write!(f, "`let`"),
+            TokenType::Rec => write!(f, "`rec`"),
+            TokenType::In => write!(f, "`in`"),
+            TokenType::Where => write!(f, "`where`"),
+            TokenType::Fix => write!(f, "`fix`"),
+            TokenType::If => write!(f, "`if`"),
+            TokenType::Then => write!(f, "`then`"),
+            TokenType::Else => write!(f, "`else`"),
             TokenType::LPar => write!(f, "`(`"),
             TokenType::RPar => write!(f, "`)`"),
             TokenType::Comment(text) => write!(f, "comment `{text}`"),
@@ -5,7 +5,7 @@ type LexerInput<'src> = std::iter::Peekable<std::str::CharIndices<'src>>;
 pub struct TokenStream<'src> {
     iter: LexerInput<'src>,
     token_vec: TokenVec<'src>,
-    errors: Vec<LexerError<'src>>,
+    errors: Vec<crate::error::Diagnostic<'src>>,
 }
 
 impl<'src> TokenStream<'src> {
@@ -66,11 +66,11 @@ impl<'src> TokenStream<'src> {
         let end = self.peek().0;
         let span = Span::new(&self.token_vec.source.text, start, end);
         self.errors
-            .push(LexerError::invalid_token(self.token_vec.source, span));
+            .push(error::invalid_token(self.token_vec.source, span));
     }
 
     fn invalid_number(&mut self, span: Span<'src>, error: std::num::ParseIntError) {
-        self.errors.push(LexerError::invalid_number(
+        self.errors.push(error::invalid_number(
             self.token_vec.source,
             span,
             &error.to_string(),
@@ -95,6 +95,7 @@ impl<'src> TokenStream<'src> {
             ("λ", 1, TokenType::Lambda),
             (".", 1, TokenType::Dot),
             ("->", 2, TokenType::Dot),
+            (":", 1, TokenType::Colon),
             ("==", 2, TokenType::Eq),
             ("=", 1, TokenType::Assign),
             ("+", 1, TokenType::Add),
@@ -129,9 +130,19 @@ impl<'src> TokenStream<'src> {
         let end = self.consume_while(|c| c.is_alphanumeric() || c == '_');
         let span = Span::new(&self.token_vec.source.text, start, end);
         let var_name = span.get_text(&self.token_vec.source.text);
-        self.token_vec
-            .tokens
-            .push(Token::new(TokenType::Ident(var_name), span));
+        // Keywords are spelled like identifiers
+        let token_type = match var_name {
+            "let" => TokenType::Let,
+            "rec" => TokenType::Rec,
+            "in" => TokenType::In,
+            "where" => TokenType::Where,
+            "fix" => TokenType::Fix,
+            "if" => TokenType::If,
+            "then" => TokenType::Then,
+            "else" => TokenType::Else,
+            _ => TokenType::Ident(var_name),
+        };
+        self.token_vec.tokens.push(Token::new(token_type, span));
     }
 
     fn push_number(&mut self, start: usize) {
@@ -155,7 +166,7 @@ impl<'src> TokenStream<'src> {
     {
         while let (index, Some(ch)) = self.peek() {
             match ch {
-                ch if "λ\\*/+().&|!-=\r\n".contains(ch) => self.push_symbol(index),
+                ch if "λ\\*/+().:&|!-=\r\n".contains(ch) => self.push_symbol(index),
                 ch if ch.is_whitespace() => self.skip_whitespace(),
                 ch if ch.is_alphabetic() => self.push_variable(index),
                 ch if ch.is_numeric() => self.push_number(index),
@@ -182,7 +193,58 @@ pub struct TokenVec<'src> {
 pub type TokenIter<'src> = std::iter::Peekable<std::slice::Iter<'src, Token<'src>>>;
 
 impl TokenVec<'_> {
-    pub fn iter(&self) -> TokenIter {
+    pub fn iter(&self) -> TokenIter<'_> {
         self.tokens.iter().peekable()
     }
+
+    // Whether the input stops in the middle of an expression: a `(` is left
+    // unclosed, or the last token still expects something after it
+    pub fn is_incomplete(&self) -> bool {
+        let mut depth = 0usize;
+        for token in &self.tokens {
+            match token.token_type {
+                TokenType::LPar => depth += 1,
+                TokenType::RPar => depth = depth.saturating_sub(1),
+                _ => {}
+            }
+        }
+        let last = self
+            .tokens
+            .iter()
+            .rev()
+            .find(|token| !matches!(token.token_type, TokenType::Comment(_) | TokenType::Eol));
+        depth > 0
+            || last.is_some_and(|token| {
+                matches!(
+                    token.token_type,
+                    TokenType::Lambda
+                        | TokenType::Dot
+                        | TokenType::Colon
+                        | TokenType::Let
+                        | TokenType::Rec
+                        | TokenType::In
+                        | TokenType::Where
+                        | TokenType::If
+                        | TokenType::Then
+                        | TokenType::Else
+                        | TokenType::Assign
+                        | TokenType::Mul
+                        | TokenType::Div
+                        | TokenType::Add
+                        | TokenType::Sub
+                        | TokenType::And
+                        | TokenType::Or
+                        | TokenType::Not
+                        | TokenType::Eq
+                        | TokenType::Neq
+                )
+            })
+    }
+
+    // Whether there is nothing but comments and line breaks
+    pub fn is_blank(&self) -> bool {
+        self.tokens
+            .iter()
+            .all(|token| matches!(token.token_type, TokenType::Comment(_) | TokenType::Eol))
+    }
 }
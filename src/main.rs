@@ -1,49 +1,290 @@
+mod debruijn;
 mod error;
+mod eval;
+mod formatter;
+mod graph;
+mod interrupt;
 mod lexer;
 mod parser;
+mod repl;
+mod resolve;
 mod source;
+mod types;
 
-fn run() {
-
-}
-
-fn main() {
-    let mut args = std::env::args();
-
-    // Read command line arguments
-    let file_name = args
-        .nth(1)
-        .expect("Error: expected source file name as first argument");
+use error::Report;
 
+// Reads, checks and loads the definitions of a file into `evaluator`, then
+// with `run_main` evaluates its `main` definition, if any. Returns whether it
+// could be loaded and run, errors are printed in `format`
+fn load_file(
+    evaluator: &mut eval::Evaluator,
+    file_name: &str,
+    format: error::Format,
+    run_main: bool,
+) -> bool {
     // Read source code
-    let source = source::Source::from_file(file_name).expect("Error: could not read source file");
+    let source = match source::Source::from_file(file_name.to_string()) {
+        Ok(source) => source,
+        Err(error) => {
+            let message = format!("could not read `{file_name}` ({error})");
+            error::emit_message(format, &message, Some(file_name));
+            return false;
+        }
+    };
 
     // Tokenize
     let tokens = match lexer::tokenise(&source) {
         Ok(tokens) => tokens,
         Err(error) => {
-            println!("{error}");
-            return;
+            error.emit(format);
+            return false;
         }
     };
 
-    // Parse
-    let ast = match parser::parse_file(&tokens) {
-        Ok(ast) => ast,
-        Err(error) => {
-            println!("{error}");
-            return;
+    // Parse, keeping what could be parsed so its names get checked as well
+    let (ast, errors) = parser::parse_file(&tokens);
+    errors.emit(format);
+
+    // Check names, warnings do not stop evaluation
+    let diagnostics = resolve::resolve(&tokens, &ast, |name| evaluator.env.get(name).is_some());
+    diagnostics.emit(format);
+    if !errors.is_empty() || diagnostics.has_errors() {
+        return false;
+    }
+
+    // Check types. Outside of typed mode they are only kept for `:type`
+    let snapshot = evaluator.types.clone();
+    let diagnostics = types::check(&tokens, &ast, &mut evaluator.types);
+    if evaluator.typed {
+        diagnostics.emit(format);
+        // Nothing is loaded, so none of the types are kept either
+        if diagnostics.has_errors() {
+            evaluator.types = snapshot;
+            return false;
+        }
+    }
+
+    // Evaluate
+    let mut origins = eval::Origins::default();
+    if let Err(diagnostics) = evaluator.load(&tokens, &ast, &mut origins) {
+        diagnostics.emit(format);
+        return false;
+    }
+    if let (true, Some(main)) = (run_main, evaluator.env.get("main").cloned()) {
+        match evaluator.eval(&main) {
+            Ok(value) => println!("{value}"),
+            Err(error) => {
+                error.report(&tokens, &origins, ast.span).emit(format);
+                return false;
+            }
         }
+    }
+    true
+}
+
+struct Options {
+    file_name: Option<String>,
+    strategy: eval::Strategy,
+    trace: bool,
+    church: bool,
+    typed: bool,
+    limits: eval::Limits,
+    error_format: error::Format,
+}
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<Options, String> {
+    let mut options = Options {
+        file_name: None,
+        strategy: eval::Strategy::default(),
+        trace: false,
+        church: false,
+        typed: false,
+        limits: eval::Limits::default(),
+        error_format: error::Format::default(),
+    };
+    while let Some(arg) = args.next() {
+        // Options taking a value accept both `--option=value` and
+        // `--option value`
+        let (option, inline_value) = match arg.split_once('=') {
+            Some((option, value)) if arg.starts_with("--") => (option, Some(value.to_string())),
+            _ => (arg.as_str(), None),
+        };
+        let mut value = || {
+            inline_value
+                .clone()
+                .or_else(|| args.next())
+                .ok_or(format!("expected value after `{option}`"))
+        };
+        if option == "--strategy" {
+            options.strategy = value()?.parse()?;
+        } else if option == "--max-steps" {
+            options.limits.max_steps = parse_limit(option, &value()?)?;
+        } else if option == "--max-size" {
+            options.limits.max_size = parse_limit(option, &value()?)?;
+        } else if option == "--timeout" {
+            options.limits.timeout = parse_limit(option, &value()?)?
+                .map(|secs| std::time::Duration::from_secs(secs as u64));
+        } else if option == "--error-format" {
+            options.error_format = value()?.parse()?;
+        } else if arg == "--trace" {
+            options.trace = true;
+        } else if arg == "--church" {
+            options.church = true;
+        } else if arg == "--typed" {
+            options.typed = true;
+        } else if arg.starts_with("--") {
+            return Err(format!("unknown option `{arg}`"));
+        } else if options.file_name.is_none() {
+            options.file_name = Some(arg);
+        } else {
+            return Err(format!("unexpected argument `{arg}`"));
+        }
+    }
+    Ok(options)
+}
+
+struct FmtOptions {
+    file_names: Vec<String>,
+    // Only report the files that are not formatted
+    check: bool,
+    style: parser::Style,
+    error_format: error::Format,
+}
+
+fn parse_fmt_args(mut args: impl Iterator<Item = String>) -> Result<FmtOptions, String> {
+    let mut options = FmtOptions {
+        file_names: Vec::new(),
+        check: false,
+        style: parser::Style::default(),
+        error_format: error::Format::default(),
     };
+    while let Some(arg) = args.next() {
+        if let Some(format) = arg.strip_prefix("--error-format=") {
+            options.error_format = format.parse()?;
+            continue;
+        }
+        match arg.as_str() {
+            "--error-format" => {
+                let format = args.next().ok_or("expected value after `--error-format`")?;
+                options.error_format = format.parse()?;
+            }
+            "--check" => options.check = true,
+            "--backslash" => options.style.backslash = true,
+            "--arrow" => options.style.arrow = true,
+            _ if arg.starts_with("--") => return Err(format!("unknown option `{arg}`")),
+            _ => options.file_names.push(arg),
+        }
+    }
+    if options.file_names.is_empty() {
+        return Err("expected a file to format".into());
+    }
+    Ok(options)
+}
+
+// Parses the value of a limit option, where 0 means no limit
+fn parse_limit(option: &str, value: &str) -> Result<Option<usize>, String> {
+    match value.parse() {
+        Ok(0) => Ok(None),
+        Ok(limit) => Ok(Some(limit)),
+        Err(_) => Err(format!("invalid value `{value}` for `{option}`")),
+    }
+}
+
+fn usage_error(error: &str) -> ! {
+    eprintln!("Error: {error}");
+    std::process::exit(2);
+}
+
+// Rewrites files in their canonical layout, or with `--check` only tells which
+// ones are not. Returns whether all of them were fine
+fn format_files(options: &FmtOptions) -> bool {
+    let format = options.error_format;
+    let mut ok = true;
+    for file_name in &options.file_names {
+        let source = match source::Source::from_file(file_name.clone()) {
+            Ok(source) => source,
+            Err(error) => {
+                let message = format!("could not read `{file_name}` ({error})");
+                error::emit_message(format, &message, Some(file_name));
+                ok = false;
+                continue;
+            }
+        };
+        let tokens = match lexer::tokenise(&source) {
+            Ok(tokens) => tokens,
+            Err(error) => {
+                error.emit(format);
+                ok = false;
+                continue;
+            }
+        };
+        let (ast, errors) = parser::parse_file(&tokens);
+        if !errors.is_empty() {
+            errors.emit(format);
+            ok = false;
+            continue;
+        }
 
-    println!("AST: {ast:#?}");
+        let (formatted, diagnostics) = formatter::format(&tokens, &ast, options.style);
+        diagnostics.emit(format);
+        if formatted == source.text {
+            continue;
+        }
+        if options.check {
+            let message = format!("`{file_name}` is not formatted");
+            match format {
+                error::Format::Human => println!("{message}"),
+                error::Format::Json => error::emit_message(format, &message, Some(file_name)),
+            }
+            ok = false;
+        } else if let Err(error) = std::fs::write(file_name, formatted) {
+            let message = format!("could not write `{file_name}` ({error})");
+            error::emit_message(format, &message, Some(file_name));
+            ok = false;
+        }
+    }
+    ok
+}
 
-    // loop {
-    //     let mut input = String::new();
-    //     std::io::stdin().read_line(&mut input).expect("Failed to read line");
-    //     let source = source::Source::from_string(input);
-    //     let tokens = lexer::tokenise(&source);
-    //     let ast = parser::parse_line(&tokens);
-    //     println!("{tokens:#?}", tokens);
-    // }
+// Terms can nest deeply before the size limit stops them, and every pass over
+// them is recursive, so the interpreter gets a larger stack than the default
+const STACK_SIZE: usize = 256 * 1024 * 1024;
+
+fn main() {
+    let interpreter = std::thread::Builder::new()
+        .stack_size(STACK_SIZE)
+        .spawn(interpret)
+        .expect("failed to start the interpreter thread");
+    if interpreter.join().is_err() {
+        std::process::exit(101);
+    }
+}
+
+fn interpret() {
+    // Read command line arguments
+    let mut args = std::env::args().skip(1).peekable();
+    if args.next_if_eq("fmt").is_some() {
+        let options = parse_fmt_args(args).unwrap_or_else(|error| usage_error(&error));
+        let formatted = format_files(&options);
+        std::process::exit(if formatted { 0 } else { 1 });
+    }
+    let options = parse_args(args).unwrap_or_else(|error| usage_error(&error));
+
+    let mut evaluator = eval::Evaluator::new();
+    evaluator.strategy = options.strategy;
+    evaluator.trace = options.trace;
+    evaluator.church = options.church;
+    evaluator.typed = options.typed;
+    evaluator.limits = options.limits;
+
+    // Without a source file start the REPL
+    match options.file_name {
+        // Programs start from their `main` definition
+        Some(file_name) => {
+            if !load_file(&mut evaluator, &file_name, options.error_format, true) {
+                std::process::exit(1);
+            }
+        }
+        None => repl::Repl::new(evaluator).run(),
+    }
 }
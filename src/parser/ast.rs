@@ -1,9 +1,64 @@
-pub type Node<'src> = Box<Ast<'src>>;
+use super::*;
+
+// A syntax tree node along with the part of the source it was parsed from
+pub struct Node<'src> {
+    pub ast: Box<Ast<'src>>,
+    pub span: lexer::Span<'src>,
+    // Like `span`, but also takes in the parentheses written around the node
+    pub outer: lexer::Span<'src>,
+}
+
+impl<'src> Node<'src> {
+    pub fn new(ast: Ast<'src>, span: lexer::Span<'src>) -> Self {
+        Self {
+            ast: Box::new(ast),
+            span,
+            outer: span,
+        }
+    }
+
+    // Builds a node that spans from the start of `lhs` to the end of `rhs`,
+    // parentheses included
+    pub fn binary(
+        build: fn(Node<'src>, Node<'src>) -> Ast<'src>,
+        lhs: Node<'src>,
+        rhs: Node<'src>,
+    ) -> Self {
+        let span = lhs.outer.join(rhs.outer);
+        Self::new(build(lhs, rhs), span)
+    }
+
+    // Name and value of a binding of a `let` or a `where`, which the parser
+    // always makes an `Assign`
+    pub fn binding(&self) -> (&'src str, &Node<'src>) {
+        match &*self.ast {
+            Ast::Assign(name, value) => (name, value),
+            _ => unreachable!("bindings are assignments"),
+        }
+    }
+}
+
+impl<'src> std::ops::Deref for Node<'src> {
+    type Target = Ast<'src>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.ast
+    }
+}
+
+impl std::fmt::Debug for Node<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Debug::fmt(&self.ast, f)?;
+        write!(f, " @ {:?}", self.span)
+    }
+}
 
 #[derive(Debug)]
 pub enum Ast<'src> {
     Var(&'src str),
     Num(u64),
+    // The fixpoint primitive: `fix f` stands for `f (fix f)`
+    Fix,
     Not(Node<'src>),
     App(Node<'src>, Node<'src>),
     Mul(Node<'src>, Node<'src>),
@@ -14,7 +69,35 @@ pub enum Ast<'src> {
     Or(Node<'src>, Node<'src>),
     Eq(Node<'src>, Node<'src>),
     Neq(Node<'src>, Node<'src>),
-    Abs(&'src str, Node<'src>),
+    // The parameter may have its type written down
+    Abs(&'src str, Option<Type<'src>>, Node<'src>),
+    // `let x = e1 in e2`. The bindings are `Assign` nodes, each one in scope
+    // in those after it and in the body
+    Let(Vec<Node<'src>>, Node<'src>),
+    // `let rec f = e1 in e2`, where every binding is in scope in all of the
+    // values as well, so that they may be mutually recursive
+    LetRec(Vec<Node<'src>>, Node<'src>),
+    // `e where x = e1`, at the end of a definition. Its bindings are scoped as
+    // in a `let` around `e`
+    Where(Node<'src>, Vec<Node<'src>>),
+    // `if c then e1 else e2`
+    If(Node<'src>, Node<'src>, Node<'src>),
     Assign(&'src str, Node<'src>),
-    Source(Vec<Ast<'src>>),
+    // `name : type`, given just before the definition of `name`
+    Signature(&'src str, Type<'src>),
+    // Comments only appear between the definitions of a source, so that the
+    // formatter can put them back. Those inside a definition come before it
+    Comment(&'src str),
+    // Stands for the part of the source a syntax error was found in, so the
+    // rest of the tree can still be checked. Trees with one are not evaluated
+    Error,
+    Source(Vec<Node<'src>>),
+}
+
+// A type written in an annotation or a signature. Names are kept as written,
+// the type checker tells base types from type variables
+#[derive(Debug)]
+pub enum Type<'src> {
+    Name(&'src str),
+    Arrow(Box<Type<'src>>, Box<Type<'src>>),
 }
@@ -1,38 +1,39 @@
 use super::*;
 
-#[derive(Debug)]
-pub struct ParserError<'src> {
-    message: String,
-    span: lexer::Span<'src>,
+use crate::error::{Diagnostic, Report};
+use crate::source;
+
+// Any token where another one was expected
+fn unexpected<'src>(message: String, span: lexer::Span<'src>) -> Diagnostic<'src> {
+    Diagnostic::error("E0003", message, span)
 }
 
-impl<'src> ParserError<'src> {
-    fn print(
-        &self,
-        f: &mut std::fmt::Formatter<'_>,
-        tokens: &'src lexer::TokenVec,
-    ) -> std::fmt::Result {
-        writeln!(f, "\x1B[1;31mParser error:\x1B[39m {}\x1B[m", self.message)?;
-        writeln!(
-            f,
-            "   \x1B[1;34m-->\x1B[m {}",
-            self.span.get_location_str(tokens.source)
-        )?;
-        writeln!(f, "    \x1B[1;34m|\x1B[m")?;
-        self.span.print(f, tokens)?;
-        writeln!(f)
-    }
+// Something other than `)` at `span`, in the parenthesis opened at `open`
+pub fn unexpected_in_paren<'src>(
+    open: lexer::Span<'src>,
+    span: lexer::Span<'src>,
+    found: String,
+) -> Diagnostic<'src> {
+    unexpected(format!("expected token `)`, found {found}"), span)
+        .with_secondary(open, "parenthesis opened here")
+}
 
-    pub fn new_end(message: String, tokens: &'src lexer::TokenVec) -> Self {
-        let end = tokens.source.text.len();
-        let span = lexer::Span::new(&tokens.source.text, end, end);
-        Self { message, span }
-    }
+// A `(` opened at `open` that is still not closed at `span`
+pub fn unclosed_paren<'src>(
+    open: lexer::Span<'src>,
+    span: lexer::Span<'src>,
+    found: String,
+) -> Diagnostic<'src> {
+    Diagnostic::error("E0004", "unclosed `(`".into(), span)
+        .with_label(format!("expected `)`, found {found}"))
+        .with_secondary(open, "opened here")
+}
 
-    pub fn new(message: String, tokens: &'src lexer::TokenVec, start: usize, end: usize) -> Self {
-        let span = lexer::Span::new(&tokens.source.text, start, end);
-        Self { message, span }
-    }
+// Where the input ends, right after its last character that is not blank
+pub fn end_of_input<'src>(tokens: &'src lexer::TokenVec) -> lexer::Span<'src> {
+    let text = &tokens.source.text;
+    let end = text.trim_end().len();
+    lexer::Span::new(text, end, end)
 }
 
 pub type ParserResult<'src> = Result<ast::Node<'src>, ParserErrorVec<'src>>;
@@ -40,7 +41,7 @@ pub type ParserResult<'src> = Result<ast::Node<'src>, ParserErrorVec<'src>>;
 #[derive(Debug)]
 pub struct ParserErrorVec<'src> {
     pub tokens: &'src lexer::TokenVec<'src>,
-    pub errors: Vec<ParserError<'src>>,
+    pub errors: Vec<Diagnostic<'src>>,
 }
 
 impl<'src> ParserErrorVec<'src> {
@@ -57,29 +58,34 @@ impl<'src> ParserErrorVec<'src> {
         start: usize,
         end: usize,
     ) -> Self {
+        let span = lexer::Span::new(&tokens.source.text, start, end);
         Self {
             tokens,
-            errors: vec![ParserError::new(message, tokens, start, end)],
+            errors: vec![unexpected(message, span)],
         }
     }
 
     pub fn single_end(message: String, tokens: &'src lexer::TokenVec) -> Self {
         Self {
             tokens,
-            errors: vec![ParserError::new_end(message, tokens)],
+            errors: vec![unexpected(message, end_of_input(tokens))],
         }
     }
 
-    pub fn get_longer_of(self, other: Self) -> Self {
-        let other_is_longer = other.errors.len() > self.errors.len()
-            || other.errors.len() == self.errors.len()
-                && !self.errors.is_empty()
-                && other.errors[0].span.start > self.errors[0].span.start;
-        if other_is_longer {
-            other
-        } else {
-            self
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+
+    pub fn push(&mut self, error: Diagnostic<'src>) {
+        self.errors.push(error);
+    }
+
+    // Suggests how to fix the last error
+    pub fn with_help(mut self, help: String) -> Self {
+        if let Some(error) = self.errors.pop() {
+            self.errors.push(error.with_help(help));
         }
+        self
     }
 
     pub fn combine(&mut self, other: Self) {
@@ -87,6 +93,16 @@ impl<'src> ParserErrorVec<'src> {
     }
 }
 
+impl Report for ParserErrorVec<'_> {
+    fn source(&self) -> &source::Source {
+        self.tokens.source
+    }
+
+    fn diagnostics(&self) -> &[Diagnostic<'_>] {
+        &self.errors
+    }
+}
+
 impl std::fmt::Display for ParserErrorVec<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         for error in &self.errors {
@@ -1,12 +1,17 @@
+pub mod ast;
 mod error;
 mod parser_state;
-mod ast;
+mod printer;
 
 use crate::lexer;
 
 use error::*;
 
-pub fn parse_file<'src>(tokens: &'src lexer::TokenVec<'src>) -> ParserResult<'src> {
+pub use printer::{Precedence, Style};
+
+pub fn parse_file<'src>(
+    tokens: &'src lexer::TokenVec<'src>,
+) -> (ast::Node<'src>, ParserErrorVec<'src>) {
     parser_state::ParserState::new(tokens).parse_file()
 }
 
@@ -9,6 +9,12 @@ pub struct ParserState<'src> {
     token_vec: &'src lexer::TokenVec<'src>,
     errors: ParserErrorVec<'src>,
     iter: lexer::TokenIter<'src>,
+    // End of the last token consumed
+    prev_end: usize,
+    // Comments skipped since they were last taken
+    comments: Vec<&'src lexer::Token<'src>>,
+    // Number of parentheses open around the current position
+    depth: usize,
 }
 
 impl<'src> ParserState<'src> {
@@ -18,60 +24,90 @@ impl<'src> ParserState<'src> {
             token_vec,
             errors: ParserErrorVec::new(token_vec),
             iter,
+            prev_end: 0,
+            comments: Vec::new(),
+            depth: 0,
         }
     }
 
     pub fn parse_line(mut self) -> ParserResult<'src> {
-        let stored = self.iter.clone();
-        let err1 = match self.parse_assignment(Flags {
+        let flags = Flags {
             ignore_newline: false,
-        }) {
-            Ok(ast) => return Ok(ast),
-            Err(err) => err,
         };
-        self.iter = stored;
-        let err2 = match self.parse_abstraction(Flags {
-            ignore_newline: false,
-        }) {
-            Ok(ast) => return Ok(ast),
-            Err(err) => err,
+        // A line that starts like a definition or a signature is one, there
+        // is no need to try it as an expression as well
+        let result = if is_signature(self.iter.clone()) {
+            self.parse_signature(flags)
+        } else if is_definition(self.iter.clone()) {
+            self.parse_assignment(flags)
+        } else {
+            self.parse_abstraction(flags)
         };
-        Err(ParserErrorVec::get_longer_of(err1, err2))
+        match result.and_then(|ast| self.parse_end().map(|()| ast)) {
+            Ok(ast) if self.errors.is_empty() => Ok(ast),
+            Ok(_) => Err(self.errors),
+            Err(err) => {
+                self.errors.combine(err);
+                Err(self.errors)
+            }
+        }
     }
 
-    pub fn parse_file(mut self) -> ParserResult<'src> {
+    // Parses every definition of a file, along with all the errors found. The
+    // definitions that could not be parsed are left as error nodes, so later
+    // passes can still look at the rest
+    pub fn parse_file(mut self) -> (ast::Node<'src>, ParserErrorVec<'src>) {
+        let flags = Flags {
+            ignore_newline: false,
+        };
         let mut asts = Vec::new();
         loop {
-            self.skip_newlines(Flags {
-                ignore_newline: true,
-            });
-            if self.peek().is_none() {
+            self.skip_blank_lines();
+            let at_end = self.peek().is_none();
+            asts.extend(self.take_comments(|_| true));
+            if at_end {
                 break;
             }
-            match self.parse_assignment(Flags {
-                ignore_newline: false,
-            }) {
-                Ok(ast) => asts.push(*ast),
+            let start = self.peek_start(flags);
+            let item = if is_signature(self.iter.clone()) {
+                self.parse_signature(flags)
+            } else {
+                self.parse_assignment(flags)
+            };
+            let ast = match item {
+                Ok(ast) => {
+                    if let Err(err) = self.parse_line_end() {
+                        self.errors.combine(err);
+                        self.sync_to_definition();
+                    }
+                    ast
+                }
                 Err(err) => {
                     self.errors.combine(err);
-                    self.sync_to_newline();
+                    self.sync_to_definition();
+                    ast::Node::new(ast::Ast::Error, self.span_from(start))
                 }
-            }
-        }
-        if self.errors.errors.is_empty() {
-            Ok(Box::new(ast::Ast::Source(asts)))
-        } else {
-            Err(self.errors)
+            };
+            let end = ast.outer.end;
+            asts.extend(self.take_comments(|comment| comment.span.start < end));
+            asts.push(ast);
+            asts.extend(self.take_comments(|_| true));
         }
+        let text = &self.token_vec.source.text;
+        let span = lexer::Span::new(text, 0, text.len());
+        (ast::Node::new(ast::Ast::Source(asts), span), self.errors)
     }
 
     fn peek(&mut self) -> Option<&'src lexer::Token<'src>> {
         loop {
             match self.iter.peek().copied() {
-                Some(lexer::Token {
-                    token_type: lexer::TokenType::Comment(_),
-                    ..
-                }) => {
+                Some(
+                    comment @ lexer::Token {
+                        token_type: lexer::TokenType::Comment(_),
+                        ..
+                    },
+                ) => {
+                    self.comments.push(comment);
                     self.iter.next();
                 }
                 token => return token,
@@ -82,15 +118,48 @@ impl<'src> ParserState<'src> {
     fn next(&mut self) -> Option<&'src lexer::Token<'src>> {
         loop {
             match self.iter.next() {
-                Some(lexer::Token {
-                    token_type: lexer::TokenType::Comment(_),
-                    ..
-                }) => {}
-                token => return token,
+                Some(
+                    comment @ lexer::Token {
+                        token_type: lexer::TokenType::Comment(_),
+                        ..
+                    },
+                ) => self.comments.push(comment),
+                Some(token) => {
+                    self.prev_end = token.span.end;
+                    return Some(token);
+                }
+                None => return None,
             }
         }
     }
 
+    // Turns the comments skipped so far that satisfy `pred` into nodes
+    fn take_comments(&mut self, pred: impl Fn(&lexer::Token) -> bool) -> Vec<ast::Node<'src>> {
+        let (taken, kept) = self.comments.iter().partition(|comment| pred(comment));
+        self.comments = kept;
+        taken
+            .into_iter()
+            .map(|comment: &lexer::Token<'src>| {
+                let text = comment.span.get_text(&self.token_vec.source.text);
+                ast::Node::new(ast::Ast::Comment(text), comment.span)
+            })
+            .collect()
+    }
+
+    // Start of the next token, or the end of input if there is none
+    fn peek_start(&mut self, flags: Flags) -> usize {
+        self.skip_newlines(flags);
+        match self.peek() {
+            Some(token) => token.span.start,
+            None => self.token_vec.source.text.len(),
+        }
+    }
+
+    // Span from `start` up to the end of the last token consumed
+    fn span_from(&self, start: usize) -> lexer::Span<'src> {
+        lexer::Span::new(&self.token_vec.source.text, start, self.prev_end)
+    }
+
     fn parse_token(
         &mut self,
         token_type: lexer::TokenType,
@@ -165,8 +234,10 @@ impl<'src> ParserState<'src> {
         if !flags.ignore_newline {
             return;
         }
+        // A line that starts a definition ends the expression before it, even
+        // if a parenthesis was left open
         while let Some(token) = self.peek() {
-            if token.token_type == lexer::TokenType::Eol {
+            if token.token_type == lexer::TokenType::Eol && !is_definition(self.iter.clone()) {
                 self.next();
             } else {
                 break;
@@ -174,35 +245,370 @@ impl<'src> ParserState<'src> {
         }
     }
 
-    fn sync_to_newline(&mut self) {
-        while let Some(token) = self.next() {
+    fn skip_blank_lines(&mut self) {
+        while let Some(token) = self.peek() {
             if token.token_type == lexer::TokenType::Eol {
+                self.next();
+            } else {
+                break;
+            }
+        }
+    }
+
+    fn parse_end(&mut self) -> Result<(), ParserErrorVec<'src>> {
+        self.skip_blank_lines();
+        match self.peek() {
+            Some(token) => Err(ParserErrorVec::single(
+                format!("expected end of input, found {}", token.token_type),
+                self.token_vec,
+                token.span.start,
+                token.span.end,
+            )),
+            None => Ok(()),
+        }
+    }
+
+    // A line that ends in an operator, `=`, `λ` or `.` continues on the next one
+    fn skip_continuation(&mut self) {
+        self.skip_newlines(Flags {
+            ignore_newline: true,
+        });
+    }
+
+    fn parse_line_end(&mut self) -> Result<(), ParserErrorVec<'src>> {
+        match self.peek() {
+            Some(token) if token.token_type != lexer::TokenType::Eol => {
+                Err(ParserErrorVec::single(
+                    format!("expected end of line, found {}", token.token_type),
+                    self.token_vec,
+                    token.span.start,
+                    token.span.end,
+                ))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    // Skips the rest of a broken definition, along with any line that does not
+    // start a new one
+    fn sync_to_definition(&mut self) {
+        while let Some(token) = self.peek() {
+            if token.token_type == lexer::TokenType::Eol && is_definition(self.iter.clone()) {
                 break;
             }
+            self.next();
+        }
+    }
+
+    // Records `err` and skips the rest of the broken expression, up to the next
+    // operator, closing parenthesis or line break, where parsing goes on. The
+    // tokens skipped become an error node
+    fn recover(
+        &mut self,
+        err: ParserErrorVec<'src>,
+        start: usize,
+        flags: Flags,
+    ) -> ast::Node<'src> {
+        self.errors.combine(err);
+        while let Some(token) = self.peek() {
+            let sync = match token.token_type {
+                lexer::TokenType::Eol => !flags.ignore_newline || is_definition(self.iter.clone()),
+                lexer::TokenType::RPar => self.depth > 0,
+                lexer::TokenType::Eq
+                | lexer::TokenType::Neq
+                | lexer::TokenType::Or
+                | lexer::TokenType::And
+                | lexer::TokenType::Add
+                | lexer::TokenType::Sub
+                | lexer::TokenType::Mul
+                | lexer::TokenType::Div => true,
+                _ => false,
+            };
+            if sync {
+                break;
+            }
+            self.next();
+        }
+        let end = self.prev_end.max(start);
+        ast::Node::new(
+            ast::Ast::Error,
+            lexer::Span::new(&self.token_vec.source.text, start, end),
+        )
+    }
+
+    // The `)` of the parenthesis opened at `open`. Anything else is skipped up
+    // to it, and the error points at both ends
+    fn parse_closing(&mut self, open: lexer::Span<'src>, flags: Flags) {
+        self.skip_newlines(flags);
+        let (span, found) = match self.peek() {
+            Some(token) if token.token_type == lexer::TokenType::RPar => {
+                self.next();
+                return;
+            }
+            Some(token) => (token.span, token.token_type.to_string()),
+            None => (end_of_input(self.token_vec), "end of input".to_string()),
+        };
+        let error = if self.skip_to_closing() {
+            unexpected_in_paren(open, span, found)
+        } else {
+            unclosed_paren(open, span, found)
+        };
+        self.errors.push(error);
+    }
+
+    // Skips to the `)` closing a parenthesis already opened, unless a line that
+    // starts a definition comes first. Returns whether it was found
+    fn skip_to_closing(&mut self) -> bool {
+        let mut depth = 0usize;
+        while let Some(token) = self.peek() {
+            match token.token_type {
+                lexer::TokenType::LPar => depth += 1,
+                lexer::TokenType::RPar if depth == 0 => {
+                    self.next();
+                    return true;
+                }
+                lexer::TokenType::RPar => depth -= 1,
+                lexer::TokenType::Eol if is_definition(self.iter.clone()) => return false,
+                _ => {}
+            }
+            self.next();
         }
+        false
     }
 
     fn parse_assignment(&mut self, flags: Flags) -> ParserResult<'src> {
         self.skip_newlines(Flags {
             ignore_newline: false,
         });
+        let start = self.peek_start(flags);
         let name = self.parse_ident(flags)?;
         self.parse_token(lexer::TokenType::Assign, flags)?;
+        self.skip_continuation();
         let expr = self.parse_abstraction(flags)?;
-        Ok(Box::new(ast::Ast::Assign(name, expr)))
+        let expr = self.parse_where(expr, start, flags)?;
+        Ok(ast::Node::new(
+            ast::Ast::Assign(name, expr),
+            self.span_from(start),
+        ))
+    }
+
+    // A `where` after the body of the definition starting at `start`, on the
+    // same line or the next one. Further bindings go on lines of their own,
+    // indented past the start of the definition
+    fn parse_where(
+        &mut self,
+        body: ast::Node<'src>,
+        start: usize,
+        flags: Flags,
+    ) -> ParserResult<'src> {
+        let mut ahead = self.iter.clone().filter(|token| {
+            !matches!(
+                token.token_type,
+                lexer::TokenType::Eol | lexer::TokenType::Comment(_)
+            )
+        });
+        if !ahead
+            .next()
+            .is_some_and(|token| token.token_type == lexer::TokenType::Where)
+        {
+            return Ok(body);
+        }
+        self.skip_blank_lines();
+        self.next();
+
+        let column = self.token_vec.source.get_line_column(start).1;
+        let mut bindings = Vec::new();
+        loop {
+            self.skip_blank_lines();
+            bindings.push(self.parse_assignment(flags)?);
+            let next = self.iter.clone().find(|token| {
+                !matches!(
+                    token.token_type,
+                    lexer::TokenType::Eol | lexer::TokenType::Comment(_)
+                )
+            });
+            let indented = next.is_some_and(|token| {
+                self.token_vec.source.get_line_column(token.span.start).1 > column
+            });
+            let on_new_line = self
+                .peek()
+                .is_some_and(|token| token.token_type == lexer::TokenType::Eol);
+            if !(on_new_line && indented && is_definition(self.iter.clone())) {
+                break;
+            }
+        }
+        let span = self.span_from(body.outer.start);
+        Ok(ast::Node::new(ast::Ast::Where(body, bindings), span))
     }
 
     fn parse_abstraction(&mut self, flags: Flags) -> ParserResult<'src> {
+        let start = self.peek_start(flags);
         if self.parse_token(lexer::TokenType::Lambda, flags).is_ok() {
-            let arg = self.parse_ident(flags)?;
-            self.parse_token(lexer::TokenType::Dot, flags)?;
+            self.skip_continuation();
+            let (arg, annotation) = match self.parse_binder(flags) {
+                Ok(binder) => binder,
+                Err(err) => return Ok(self.recover(err, start, flags)),
+            };
+            self.skip_continuation();
             let body = self.parse_abstraction(flags)?;
-            Ok(Box::new(ast::Ast::Abs(arg, body)))
+            Ok(ast::Node::new(
+                ast::Ast::Abs(arg, annotation, body),
+                self.span_from(start),
+            ))
+        } else if self.parse_token(lexer::TokenType::Let, flags).is_ok() {
+            self.parse_let(start, flags)
+        } else if self.parse_token(lexer::TokenType::If, flags).is_ok() {
+            self.parse_if(start, flags)
         } else {
             self.parse_comparison(flags)
         }
     }
 
+    // The rest of a `let` or a `let rec`, up to the end of its body. Several
+    // bindings go on lines of their own, so the `let` must then be inside
+    // parentheses
+    fn parse_let(&mut self, start: usize, flags: Flags) -> ParserResult<'src> {
+        let recursive = self.parse_token(lexer::TokenType::Rec, flags).is_ok();
+        let mut bindings = Vec::new();
+        loop {
+            // The first binding may start on the line after `let`, even though
+            // it looks like a definition
+            self.skip_blank_lines();
+            bindings.push(self.parse_assignment(flags)?);
+            if flags.ignore_newline {
+                self.skip_blank_lines();
+                if is_definition(self.iter.clone()) {
+                    continue;
+                }
+            }
+            match self.parse_token(lexer::TokenType::In, flags) {
+                Ok(()) => break,
+                Err(err)
+                    if self
+                        .peek()
+                        .is_some_and(|token| token.token_type == lexer::TokenType::Eol) =>
+                {
+                    return Err(err.with_help(
+                        "put the `let` inside parentheses to give its bindings lines of their own"
+                            .into(),
+                    ));
+                }
+                Err(err) => return Err(err),
+            }
+        }
+        self.skip_continuation();
+        let body = self.parse_abstraction(flags)?;
+        let ast = if recursive {
+            ast::Ast::LetRec(bindings, body)
+        } else {
+            ast::Ast::Let(bindings, body)
+        };
+        Ok(ast::Node::new(ast, self.span_from(start)))
+    }
+
+    // The rest of an `if`. Like the body of an abstraction, the `else` branch
+    // reaches as far right as it can. `then` and `else` may start lines of
+    // their own
+    fn parse_if(&mut self, start: usize, flags: Flags) -> ParserResult<'src> {
+        self.skip_continuation();
+        let condition = self.parse_abstraction(flags)?;
+        self.skip_continuation();
+        self.parse_token(lexer::TokenType::Then, flags)?;
+        self.skip_continuation();
+        let then = self.parse_abstraction(flags)?;
+        self.skip_continuation();
+        self.parse_token(lexer::TokenType::Else, flags)?;
+        self.skip_continuation();
+        let otherwise = self.parse_abstraction(flags)?;
+        Ok(ast::Node::new(
+            ast::Ast::If(condition, then, otherwise),
+            self.span_from(start),
+        ))
+    }
+
+    // The `x.` or `x: type.` after a `λ`. Since `->` is also a `.`, the one
+    // after a type is always taken as part of it
+    fn parse_binder(
+        &mut self,
+        flags: Flags,
+    ) -> Result<(&'src str, Option<ast::Type<'src>>), ParserErrorVec<'src>> {
+        let arg = self.parse_ident(flags)?;
+        let mut annotation = None;
+        if self.parse_token(lexer::TokenType::Colon, flags).is_ok() {
+            annotation = Some(self.parse_type(flags)?);
+        }
+        if let Err(err) = self.parse_token(lexer::TokenType::Dot, flags) {
+            return Err(match self.peek() {
+                Some(&lexer::Token {
+                    token_type: lexer::TokenType::Ident(next),
+                    ..
+                }) => err.with_help(format!(
+                    "an abstraction takes a single parameter, write `λ{arg}. λ{next}. ...`"
+                )),
+                _ => err,
+            });
+        }
+        Ok((arg, annotation))
+    }
+
+    fn parse_signature(&mut self, flags: Flags) -> ParserResult<'src> {
+        let start = self.peek_start(flags);
+        let name = self.parse_ident(flags)?;
+        self.parse_token(lexer::TokenType::Colon, flags)?;
+        self.skip_continuation();
+        let ty = self.parse_type(flags)?;
+        Ok(ast::Node::new(
+            ast::Ast::Signature(name, ty),
+            self.span_from(start),
+        ))
+    }
+
+    // Arrows are right-associative, `a -> b -> c` is `a -> (b -> c)`
+    fn parse_type(&mut self, flags: Flags) -> Result<ast::Type<'src>, ParserErrorVec<'src>> {
+        let lhs = self.parse_type_atom(flags)?;
+        self.skip_newlines(flags);
+        match self.peek() {
+            Some(token) if token.span.get_text(&self.token_vec.source.text) == "->" => {
+                self.next();
+                self.skip_continuation();
+                let rhs = self.parse_type(flags)?;
+                Ok(ast::Type::Arrow(Box::new(lhs), Box::new(rhs)))
+            }
+            _ => Ok(lhs),
+        }
+    }
+
+    fn parse_type_atom(&mut self, flags: Flags) -> Result<ast::Type<'src>, ParserErrorVec<'src>> {
+        self.skip_newlines(flags);
+        match self.peek() {
+            Some(token) if token.token_type == lexer::TokenType::LPar => {
+                self.next();
+                let ty = self.parse_type(Flags {
+                    ignore_newline: true,
+                })?;
+                self.parse_token(lexer::TokenType::RPar, flags)?;
+                Ok(ty)
+            }
+            Some(&lexer::Token {
+                token_type: lexer::TokenType::Ident(name),
+                ..
+            }) => {
+                self.next();
+                Ok(ast::Type::Name(name))
+            }
+            Some(token) => Err(ParserErrorVec::single(
+                format!("expected type, found {}", token.token_type),
+                self.token_vec,
+                token.span.start,
+                token.span.end,
+            )),
+            None => Err(ParserErrorVec::single_end(
+                "expected type, found end of input".into(),
+                self.token_vec,
+            )),
+        }
+    }
+
     fn parse_comparison(&mut self, flags: Flags) -> ParserResult<'src> {
         let mut expr = self.parse_disjunction(flags)?;
         loop {
@@ -210,11 +616,13 @@ impl<'src> ParserState<'src> {
             match self.peek() {
                 Some(token) if token.token_type == lexer::TokenType::Eq => {
                     self.next();
-                    expr = Box::new(ast::Ast::Eq(expr, self.parse_disjunction(flags)?));
+                    self.skip_continuation();
+                    expr = ast::Node::binary(ast::Ast::Eq, expr, self.parse_disjunction(flags)?);
                 }
                 Some(token) if token.token_type == lexer::TokenType::Neq => {
                     self.next();
-                    expr = Box::new(ast::Ast::Neq(expr, self.parse_disjunction(flags)?));
+                    self.skip_continuation();
+                    expr = ast::Node::binary(ast::Ast::Neq, expr, self.parse_disjunction(flags)?);
                 }
                 _ => break,
             }
@@ -229,7 +637,8 @@ impl<'src> ParserState<'src> {
             match self.peek() {
                 Some(token) if token.token_type == lexer::TokenType::Or => {
                     self.next();
-                    expr = Box::new(ast::Ast::Or(expr, self.parse_conjunction(flags)?));
+                    self.skip_continuation();
+                    expr = ast::Node::binary(ast::Ast::Or, expr, self.parse_conjunction(flags)?);
                 }
                 _ => break,
             }
@@ -244,7 +653,8 @@ impl<'src> ParserState<'src> {
             match self.peek() {
                 Some(token) if token.token_type == lexer::TokenType::And => {
                     self.next();
-                    expr = Box::new(ast::Ast::And(expr, self.parse_sum(flags)?));
+                    self.skip_continuation();
+                    expr = ast::Node::binary(ast::Ast::And, expr, self.parse_sum(flags)?);
                 }
                 _ => break,
             }
@@ -259,11 +669,13 @@ impl<'src> ParserState<'src> {
             match self.peek() {
                 Some(token) if token.token_type == lexer::TokenType::Add => {
                     self.next();
-                    expr = Box::new(ast::Ast::Add(expr, self.parse_product(flags)?));
+                    self.skip_continuation();
+                    expr = ast::Node::binary(ast::Ast::Add, expr, self.parse_product(flags)?);
                 }
                 Some(token) if token.token_type == lexer::TokenType::Sub => {
                     self.next();
-                    expr = Box::new(ast::Ast::Sub(expr, self.parse_product(flags)?));
+                    self.skip_continuation();
+                    expr = ast::Node::binary(ast::Ast::Sub, expr, self.parse_product(flags)?);
                 }
                 _ => break,
             }
@@ -278,11 +690,13 @@ impl<'src> ParserState<'src> {
             match self.peek() {
                 Some(token) if token.token_type == lexer::TokenType::Mul => {
                     self.next();
-                    expr = Box::new(ast::Ast::Mul(expr, self.parse_application(flags)?));
+                    self.skip_continuation();
+                    expr = ast::Node::binary(ast::Ast::Mul, expr, self.parse_application(flags)?);
                 }
                 Some(token) if token.token_type == lexer::TokenType::Div => {
                     self.next();
-                    expr = Box::new(ast::Ast::Div(expr, self.parse_application(flags)?));
+                    self.skip_continuation();
+                    expr = ast::Node::binary(ast::Ast::Div, expr, self.parse_application(flags)?);
                 }
                 _ => break,
             }
@@ -293,7 +707,7 @@ impl<'src> ParserState<'src> {
     fn parse_application(&mut self, flags: Flags) -> ParserResult<'src> {
         let mut expr = self.parse_unary(flags)?;
         while self.is_unary(flags) {
-            expr = Box::new(ast::Ast::App(expr, self.parse_unary(flags)?));
+            expr = ast::Node::binary(ast::Ast::App, expr, self.parse_unary(flags)?);
         }
         Ok(expr)
     }
@@ -307,6 +721,7 @@ impl<'src> ParserState<'src> {
                     | lexer::TokenType::Not
                     | lexer::TokenType::Ident(_)
                     | lexer::TokenType::Num(_)
+                    | lexer::TokenType::Fix
             ),
             None => false,
         }
@@ -317,34 +732,92 @@ impl<'src> ParserState<'src> {
         match self.peek() {
             Some(token) if token.token_type == lexer::TokenType::LPar => {
                 self.next();
+                self.depth += 1;
                 let expr = self.parse_abstraction(Flags {
                     ignore_newline: true,
-                })?;
-                self.parse_token(lexer::TokenType::RPar, flags)?;
+                });
+                self.depth -= 1;
+                let mut expr = expr?;
+                self.parse_closing(token.span, flags);
+                // The parentheses only count when the expression is part of
+                // a bigger one
+                expr.outer = self.span_from(token.span.start);
                 Ok(expr)
             }
             Some(token) if token.token_type == lexer::TokenType::Not => {
                 self.next();
-                Ok(Box::new(ast::Ast::Not(self.parse_unary(flags)?)))
+                self.skip_continuation();
+                let expr = self.parse_unary(flags)?;
+                let span = token.span.join(expr.outer);
+                Ok(ast::Node::new(ast::Ast::Not(expr), span))
             }
-            Some(lexer::Token {
-                token_type: lexer::TokenType::Ident(_),
-                ..
-            }) => Ok(Box::new(ast::Ast::Var(self.parse_ident(flags)?))),
-            Some(lexer::Token {
-                token_type: lexer::TokenType::Num(_),
-                ..
-            }) => Ok(Box::new(ast::Ast::Num(self.parse_number(flags)?))),
-            Some(token) => Err(ParserErrorVec::single(
-                format!("expected expression, found {}", token.token_type),
-                self.token_vec,
-                token.span.start,
-                token.span.end,
+            Some(
+                token @ lexer::Token {
+                    token_type: lexer::TokenType::Ident(_),
+                    ..
+                },
+            ) => Ok(ast::Node::new(
+                ast::Ast::Var(self.parse_ident(flags)?),
+                token.span,
             )),
-            None => Err(ParserErrorVec::single_end(
-                "expected expression, found end of input".into(),
-                self.token_vec,
+            Some(
+                token @ lexer::Token {
+                    token_type: lexer::TokenType::Num(_),
+                    ..
+                },
+            ) => Ok(ast::Node::new(
+                ast::Ast::Num(self.parse_number(flags)?),
+                token.span,
             )),
+            Some(token) if token.token_type == lexer::TokenType::Fix => {
+                self.next();
+                Ok(ast::Node::new(ast::Ast::Fix, token.span))
+            }
+            Some(token) => {
+                let err = ParserErrorVec::single(
+                    format!("expected expression, found {}", token.token_type),
+                    self.token_vec,
+                    token.span.start,
+                    token.span.end,
+                );
+                Ok(self.recover(err, token.span.start, flags))
+            }
+            None => {
+                let err = ParserErrorVec::single_end(
+                    "expected expression, found end of input".into(),
+                    self.token_vec,
+                );
+                Ok(self.recover(err, end_of_input(self.token_vec).start, flags))
+            }
         }
     }
 }
+
+// Whether `tokens` start with `Ident =` or `Ident :`, that is a definition or
+// its signature, past line breaks and comments
+fn is_definition<'src>(tokens: impl Iterator<Item = &'src lexer::Token<'src>>) -> bool {
+    matches!(
+        after_name(tokens),
+        Some(lexer::TokenType::Assign | lexer::TokenType::Colon)
+    )
+}
+
+fn is_signature<'src>(tokens: impl Iterator<Item = &'src lexer::Token<'src>>) -> bool {
+    matches!(after_name(tokens), Some(lexer::TokenType::Colon))
+}
+
+// The token after the identifier `tokens` start with, if they do
+fn after_name<'src>(
+    tokens: impl Iterator<Item = &'src lexer::Token<'src>>,
+) -> Option<&'src lexer::TokenType<'src>> {
+    let mut tokens = tokens.filter(|token| {
+        !matches!(
+            token.token_type,
+            lexer::TokenType::Eol | lexer::TokenType::Comment(_)
+        )
+    });
+    match tokens.next()?.token_type {
+        lexer::TokenType::Ident(_) => tokens.next().map(|token| &token.token_type),
+        _ => None,
+    }
+}
@@ -0,0 +1,366 @@
+use super::*;
+
+use ast::{Ast, Node};
+
+// Binding strength of each syntactic level, loosest first, as in `ParserState`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Precedence {
+    Abstraction,
+    Comparison,
+    Disjunction,
+    Conjunction,
+    Sum,
+    Product,
+    Application,
+    Unary,
+}
+
+impl Precedence {
+    pub fn next(self) -> Self {
+        match self {
+            Precedence::Abstraction => Precedence::Comparison,
+            Precedence::Comparison => Precedence::Disjunction,
+            Precedence::Disjunction => Precedence::Conjunction,
+            Precedence::Conjunction => Precedence::Sum,
+            Precedence::Sum => Precedence::Product,
+            Precedence::Product => Precedence::Application,
+            Precedence::Application | Precedence::Unary => Precedence::Unary,
+        }
+    }
+}
+
+// Spelling of the abstraction syntax, which the lexer accepts either way
+#[derive(Debug, Clone, Copy, Default)]
+pub struct Style {
+    // `\x` instead of `λx`
+    pub backslash: bool,
+    // `λx -> body` instead of `λx. body`
+    pub arrow: bool,
+}
+
+// Source text of a tree, with only the parentheses the precedence levels call
+// for. Parsing it gives back the same tree
+pub struct Pretty<'a, 'src> {
+    ast: &'a Ast<'src>,
+    style: Style,
+}
+
+impl<'src> Ast<'src> {
+    pub fn pretty(&self, style: Style) -> Pretty<'_, 'src> {
+        Pretty { ast: self, style }
+    }
+
+    // Like `pretty`, starting `indent` levels in, but with lines kept within
+    // `width` columns where they can be. Operations are broken before their
+    // operators, applications before each argument, and conditionals before
+    // `then` and `else`. What follows a `λ` or a keyword goes one level
+    // further in
+    pub fn pretty_within(&self, style: Style, indent: usize, width: usize) -> String {
+        let mut text = "    ".repeat(indent);
+        self.print_within(&mut text, style, Precedence::Abstraction, indent, width);
+        text
+    }
+
+    fn print_within(
+        &self,
+        text: &mut String,
+        style: Style,
+        prec: Precedence,
+        indent: usize,
+        width: usize,
+    ) {
+        let flat = Flat {
+            ast: self,
+            style,
+            prec,
+            indent,
+        }
+        .to_string();
+        let column = text.chars().rev().take_while(|&c| c != '\n').count();
+        let breakable =
+            matches!(self, Ast::App(..) | Ast::Abs(..) | Ast::If(..)) || self.binary().is_some();
+        if !breakable || flat.contains('\n') || column + flat.chars().count() <= width {
+            text.push_str(&flat);
+            return;
+        }
+
+        let parens = self.precedence() < prec;
+        if parens {
+            text.push('(');
+        }
+        match self {
+            Ast::App(..) => {
+                let mut args = Vec::new();
+                let mut func = self;
+                while let Ast::App(lhs, arg) = func {
+                    args.push(arg);
+                    func = lhs;
+                }
+                func.print_within(text, style, Precedence::Application, indent, width);
+                for arg in args.into_iter().rev() {
+                    break_line(text, indent + 1);
+                    arg.print_within(text, style, Precedence::Unary, indent + 1, width);
+                }
+            }
+            // Curried abstractions share a level
+            Ast::Abs(..) => {
+                let mut body = self;
+                while let Ast::Abs(arg, annotation, inner) = body {
+                    write_binder(text, style, arg, annotation.as_ref())
+                        .expect("writing to a string");
+                    body = inner;
+                }
+                body.print_within(text, style, Precedence::Abstraction, indent + 1, width);
+            }
+            Ast::If(condition, then, otherwise) => {
+                let inner = indent + 1;
+                text.push_str("if ");
+                condition.print_within(text, style, Precedence::Abstraction, inner, width);
+                break_line(text, indent);
+                text.push_str("then ");
+                then.print_within(text, style, Precedence::Abstraction, inner, width);
+                break_line(text, indent);
+                text.push_str("else ");
+                otherwise.print_within(text, style, Precedence::Abstraction, inner, width);
+            }
+            _ => {
+                // The whole chain of operators of the same level, which are
+                // all left-associative
+                let own = self.precedence();
+                let mut operands = Vec::new();
+                let mut lhs = self;
+                while let Some((symbol, next, rhs)) =
+                    lhs.binary().filter(|_| lhs.precedence() == own)
+                {
+                    operands.push((symbol, rhs));
+                    lhs = next;
+                }
+                lhs.print_within(text, style, own, indent, width);
+                for (symbol, rhs) in operands.into_iter().rev() {
+                    break_line(text, indent);
+                    text.push_str(&format!("{symbol} "));
+                    rhs.print_within(text, style, own.next(), indent, width);
+                }
+            }
+        }
+        if parens {
+            text.push(')');
+        }
+    }
+
+    // `indent` is the number of levels of indentation of the lines bindings
+    // are broken over
+    fn print(
+        &self,
+        f: &mut std::fmt::Formatter<'_>,
+        style: Style,
+        prec: Precedence,
+        indent: usize,
+    ) -> std::fmt::Result {
+        let own = self.precedence();
+        // Several bindings of a `let` can only go on lines of their own inside
+        // parentheses
+        let parens = own < prec
+            || matches!(self, Ast::Let(bindings, _) | Ast::LetRec(bindings, _) if bindings.len() > 1);
+        if parens {
+            write!(f, "(")?;
+        }
+        match self {
+            Ast::Var(name) => write!(f, "{name}")?,
+            Ast::Num(num) => write!(f, "{num}")?,
+            Ast::Fix => write!(f, "fix")?,
+            Ast::Not(expr) => {
+                write!(f, "!")?;
+                expr.print(f, style, Precedence::Unary, indent)?;
+            }
+            Ast::App(func, arg) => {
+                func.print(f, style, Precedence::Application, indent)?;
+                write!(f, " ")?;
+                arg.print(f, style, Precedence::Unary, indent)?;
+            }
+            Ast::Abs(arg, annotation, body) => {
+                write_binder(f, style, arg, annotation.as_ref())?;
+                body.print(f, style, Precedence::Abstraction, indent)?;
+            }
+            Ast::Let(bindings, body) | Ast::LetRec(bindings, body) => {
+                let keyword = match self {
+                    Ast::LetRec(..) => "let rec",
+                    _ => "let",
+                };
+                write!(f, "{keyword}")?;
+                if let [binding] = &bindings[..] {
+                    write!(f, " ")?;
+                    binding.print(f, style, Precedence::Abstraction, indent)?;
+                    write!(f, " in ")?;
+                } else {
+                    for binding in bindings {
+                        new_line(f, indent + 1)?;
+                        binding.print(f, style, Precedence::Abstraction, indent + 1)?;
+                    }
+                    new_line(f, indent)?;
+                    write!(f, "in ")?;
+                }
+                body.print(f, style, Precedence::Abstraction, indent)?;
+            }
+            Ast::If(condition, then, otherwise) => {
+                write!(f, "if ")?;
+                condition.print(f, style, Precedence::Abstraction, indent)?;
+                write!(f, " then ")?;
+                then.print(f, style, Precedence::Abstraction, indent)?;
+                write!(f, " else ")?;
+                otherwise.print(f, style, Precedence::Abstraction, indent)?;
+            }
+            Ast::Where(body, bindings) => {
+                body.print(f, style, Precedence::Abstraction, indent)?;
+                if let [binding] = &bindings[..] {
+                    write!(f, " where ")?;
+                    binding.print(f, style, Precedence::Abstraction, indent)?;
+                } else {
+                    new_line(f, indent + 1)?;
+                    write!(f, "where")?;
+                    for binding in bindings {
+                        new_line(f, indent + 2)?;
+                        binding.print(f, style, Precedence::Abstraction, indent + 2)?;
+                    }
+                }
+            }
+            Ast::Assign(name, expr) => {
+                write!(f, "{name} = ")?;
+                expr.print(f, style, Precedence::Abstraction, indent)?;
+            }
+            Ast::Signature(name, ty) => write!(f, "{name} : {ty}")?,
+            Ast::Comment(text) => write!(f, "{}", text.trim_end())?,
+            Ast::Error => write!(f, "<error>")?,
+            Ast::Source(asts) => {
+                for ast in asts {
+                    ast.print(f, style, Precedence::Abstraction, indent)?;
+                    writeln!(f)?;
+                }
+            }
+            _ => {
+                let (symbol, lhs, rhs) = self.binary().expect("other nodes are binary");
+                // All binary operators are left-associative
+                lhs.print(f, style, own, indent)?;
+                write!(f, " {symbol} ")?;
+                rhs.print(f, style, own.next(), indent)?;
+            }
+        }
+        if parens {
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
+
+    fn precedence(&self) -> Precedence {
+        match self {
+            Ast::Var(_) | Ast::Num(_) | Ast::Fix | Ast::Not(_) | Ast::Error => Precedence::Unary,
+            Ast::App(..) => Precedence::Application,
+            Ast::Mul(..) | Ast::Div(..) => Precedence::Product,
+            Ast::Add(..) | Ast::Sub(..) => Precedence::Sum,
+            Ast::And(..) => Precedence::Conjunction,
+            Ast::Or(..) => Precedence::Disjunction,
+            Ast::Eq(..) | Ast::Neq(..) => Precedence::Comparison,
+            Ast::Abs(..)
+            | Ast::Let(..)
+            | Ast::LetRec(..)
+            | Ast::Where(..)
+            | Ast::If(..)
+            | Ast::Assign(..)
+            | Ast::Signature(..)
+            | Ast::Comment(_)
+            | Ast::Source(_) => Precedence::Abstraction,
+        }
+    }
+
+    // Operator symbol and operands of a binary operation
+    fn binary(&self) -> Option<(&'static str, &Node<'src>, &Node<'src>)> {
+        let (symbol, lhs, rhs) = match self {
+            Ast::Mul(lhs, rhs) => ("*", lhs, rhs),
+            Ast::Div(lhs, rhs) => ("/", lhs, rhs),
+            Ast::Add(lhs, rhs) => ("+", lhs, rhs),
+            Ast::Sub(lhs, rhs) => ("-", lhs, rhs),
+            Ast::And(lhs, rhs) => ("&&", lhs, rhs),
+            Ast::Or(lhs, rhs) => ("||", lhs, rhs),
+            Ast::Eq(lhs, rhs) => ("==", lhs, rhs),
+            Ast::Neq(lhs, rhs) => ("!=", lhs, rhs),
+            _ => return None,
+        };
+        Some((symbol, lhs, rhs))
+    }
+}
+
+impl std::fmt::Display for Pretty<'_, '_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.ast.print(f, self.style, Precedence::Abstraction, 0)
+    }
+}
+
+// A subtree printed on one line where it is nested, for `print_within`
+struct Flat<'a, 'src> {
+    ast: &'a Ast<'src>,
+    style: Style,
+    prec: Precedence,
+    indent: usize,
+}
+
+impl std::fmt::Display for Flat<'_, '_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.ast.print(f, self.style, self.prec, self.indent)
+    }
+}
+
+// `λx. ` and its variants, up to the body
+fn write_binder(
+    f: &mut impl std::fmt::Write,
+    style: Style,
+    arg: &str,
+    annotation: Option<&ast::Type>,
+) -> std::fmt::Result {
+    let lambda = if style.backslash { "\\" } else { "λ" };
+    // After a type, `->` would be read as part of it
+    let dot = if style.arrow && annotation.is_none() {
+        " ->"
+    } else {
+        "."
+    };
+    write!(f, "{lambda}{arg}")?;
+    if let Some(ty) = annotation {
+        write!(f, ": {ty}")?;
+    }
+    write!(f, "{dot} ")
+}
+
+fn new_line(f: &mut std::fmt::Formatter<'_>, indent: usize) -> std::fmt::Result {
+    writeln!(f)?;
+    write!(f, "{}", "    ".repeat(indent))
+}
+
+fn break_line(text: &mut String, indent: usize) {
+    text.push('\n');
+    text.push_str(&"    ".repeat(indent));
+}
+
+impl std::fmt::Display for Ast<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.pretty(Style::default()).fmt(f)
+    }
+}
+
+impl std::fmt::Display for Node<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        std::fmt::Display::fmt(&self.ast, f)
+    }
+}
+
+impl std::fmt::Display for ast::Type<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ast::Type::Name(name) => write!(f, "{name}"),
+            // Arrows are right-associative
+            ast::Type::Arrow(lhs, rhs) => match **lhs {
+                ast::Type::Arrow(..) => write!(f, "({lhs}) -> {rhs}"),
+                ast::Type::Name(_) => write!(f, "{lhs} -> {rhs}"),
+            },
+        }
+    }
+}
@@ -0,0 +1,117 @@
+use crate::{eval, lexer, source};
+
+use super::COMMANDS;
+
+// Ways to finish the word that ends at the cursor
+pub struct Completion {
+    // Where the word starts, as a byte offset in the text before the cursor
+    pub start: usize,
+    // Sorted and without duplicates
+    pub candidates: Vec<String>,
+}
+
+// Completes the word before the cursor. `pending` holds the lines already
+// entered for the same input, so that binders on them count too. A line
+// starting with `:` completes command names instead
+pub fn complete(env: &eval::Env, pending: &str, before: &str) -> Option<Completion> {
+    if pending.is_empty() {
+        if let Some(command) = before.strip_prefix(':') {
+            if command.contains(char::is_whitespace) {
+                return None;
+            }
+            return Some(Completion {
+                start: 1,
+                candidates: matching(command, COMMANDS.iter().copied()),
+            });
+        }
+    }
+
+    let text = format!("{pending}{before}");
+    let source = source::Source::from_string(text);
+    let tokens = match lexer::tokenise(&source) {
+        Ok(tokens) => tokens,
+        Err(error) => error.tokens,
+    };
+    let end = source.text.len();
+
+    // The word is the identifier the cursor is at the end of, if any
+    let mut prefix = "";
+    if let Some(token) = tokens.tokens.last().filter(|token| token.span.end == end) {
+        match token.token_type {
+            lexer::TokenType::Ident(name) => prefix = name,
+            // It may still be the start of a name, like `in` of `inc`
+            lexer::TokenType::Let
+            | lexer::TokenType::Rec
+            | lexer::TokenType::In
+            | lexer::TokenType::Where
+            | lexer::TokenType::Fix
+            | lexer::TokenType::If
+            | lexer::TokenType::Then
+            | lexer::TokenType::Else => prefix = token.span.get_text(&source.text),
+            lexer::TokenType::Num(_) | lexer::TokenType::Comment(_) => return None,
+            _ => {}
+        }
+    }
+    // The start of the word may fall in the pending lines only if `before` is
+    // empty, in which case there is no word
+    let start = (end - prefix.len()).checked_sub(pending.len())?;
+
+    let globals = env.names().map(|name| &**name);
+    let bound = bound_names(&tokens);
+    Some(Completion {
+        start,
+        candidates: matching(prefix, globals.chain(bound)),
+    })
+}
+
+fn matching<'a>(prefix: &str, names: impl Iterator<Item = &'a str>) -> Vec<String> {
+    let mut candidates: Vec<String> = names
+        .filter(|name| name.starts_with(prefix))
+        .map(String::from)
+        .collect();
+    candidates.sort();
+    candidates.dedup();
+    candidates
+}
+
+// Names bound by the abstractions around the end of the input. The body of an
+// abstraction reaches as far right as it can, so a binder stays in scope until
+// the parenthesis around it is closed
+fn bound_names<'src>(tokens: &lexer::TokenVec<'src>) -> Vec<&'src str> {
+    // Binders with the parenthesis depth at which they appear
+    let mut bound = Vec::new();
+    let mut depth = 0usize;
+    for (i, token) in tokens.tokens.iter().enumerate() {
+        match token.token_type {
+            lexer::TokenType::LPar => depth += 1,
+            lexer::TokenType::RPar => {
+                depth = depth.saturating_sub(1);
+                bound.retain(|&(_, binder_depth)| binder_depth <= depth);
+            }
+            // The binder may have a type annotation, as in `λx: Num.`
+            lexer::TokenType::Lambda => {
+                if let [binder, after, ..] = &tokens.tokens[i + 1..] {
+                    if let (
+                        lexer::TokenType::Ident(name),
+                        lexer::TokenType::Dot | lexer::TokenType::Colon,
+                    ) = (&binder.token_type, &after.token_type)
+                    {
+                        bound.push((*name, depth));
+                    }
+                }
+            }
+            // So does the body of a `let`, and of a `let rec` past the `rec`
+            lexer::TokenType::Let | lexer::TokenType::Rec => {
+                if let [binder, assign, ..] = &tokens.tokens[i + 1..] {
+                    if let (lexer::TokenType::Ident(name), lexer::TokenType::Assign) =
+                        (&binder.token_type, &assign.token_type)
+                    {
+                        bound.push((*name, depth));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+    bound.into_iter().map(|(name, _)| name).collect()
+}
@@ -0,0 +1,337 @@
+use super::completion::Completion;
+use super::history::History;
+use super::terminal::{self, Key, RawMode};
+use crate::{lexer, source};
+
+use std::io::{IsTerminal, Write};
+
+pub enum ReadLine {
+    Line(String),
+    // Ctrl-C: the input typed so far should be dropped
+    Interrupted,
+    // Ctrl-D on an empty line, or the end of piped input
+    Eof,
+}
+
+// Completes the text before the cursor
+pub type Complete<'a> = &'a dyn Fn(&str) -> Option<Completion>;
+
+// Reads lines from the terminal with readline-like editing: cursor movement,
+// history and its Ctrl-R search, tab completion and syntax highlighting. When
+// the input is not a terminal, lines are read as they come
+pub struct Editor {
+    history: History,
+}
+
+impl Editor {
+    pub fn new() -> Self {
+        Self {
+            history: History::load(),
+        }
+    }
+
+    pub fn read_line(&mut self, prompt: &str, complete: Complete) -> std::io::Result<ReadLine> {
+        if !std::io::stdin().is_terminal() || !std::io::stdout().is_terminal() {
+            return read_plain(prompt);
+        }
+        let Ok(raw_mode) = RawMode::enable() else {
+            return read_plain(prompt);
+        };
+        let mut edit = Edit {
+            prompt,
+            line: Vec::new(),
+            cursor: 0,
+            history: &self.history,
+            position: self.history.entries.len(),
+            draft: Vec::new(),
+            search: None,
+            complete,
+        };
+        let result = edit.run();
+        drop(raw_mode);
+
+        if let Ok(ReadLine::Line(line)) = &result {
+            self.history.add(line);
+        }
+        result
+    }
+}
+
+fn read_plain(prompt: &str) -> std::io::Result<ReadLine> {
+    print!("{prompt}");
+    std::io::stdout().flush()?;
+    let mut line = String::new();
+    if std::io::stdin().read_line(&mut line)? == 0 {
+        return Ok(ReadLine::Eof);
+    }
+    let len = line.trim_end_matches(['\r', '\n']).len();
+    line.truncate(len);
+    Ok(ReadLine::Line(line))
+}
+
+// State of the line being edited
+struct Edit<'a> {
+    prompt: &'a str,
+    line: Vec<char>,
+    // Position of the cursor in `line`, in characters
+    cursor: usize,
+    history: &'a History,
+    // History entry shown, `entries.len()` being the line being typed
+    position: usize,
+    // The line being typed, kept while browsing the history
+    draft: Vec<char>,
+    search: Option<Search>,
+    complete: Complete<'a>,
+}
+
+// A Ctrl-R search backwards through the history
+struct Search {
+    query: String,
+    found: Option<usize>,
+}
+
+impl Edit<'_> {
+    fn run(&mut self) -> std::io::Result<ReadLine> {
+        let mut stdin = std::io::stdin().lock();
+        loop {
+            self.render()?;
+            let Some(key) = terminal::read_key(&mut stdin)? else {
+                return Ok(ReadLine::Eof);
+            };
+            let result = if self.search.is_some() {
+                self.search_key(key)
+            } else {
+                self.key(key)
+            };
+            if let Some(result) = result {
+                self.finish(&result)?;
+                return Ok(result);
+            }
+        }
+    }
+
+    // Handles a key while editing, returning the result if it ends the line
+    fn key(&mut self, key: Key) -> Option<ReadLine> {
+        match key {
+            Key::Enter => return Some(ReadLine::Line(self.line.iter().collect())),
+            Key::Ctrl('c') => return Some(ReadLine::Interrupted),
+            Key::Ctrl('d') if self.line.is_empty() => return Some(ReadLine::Eof),
+            Key::Char(c) => {
+                self.line.insert(self.cursor, c);
+                self.cursor += 1;
+            }
+            Key::Backspace | Key::Ctrl('h') if self.cursor > 0 => {
+                self.cursor -= 1;
+                self.line.remove(self.cursor);
+            }
+            Key::Delete | Key::Ctrl('d') if self.cursor < self.line.len() => {
+                self.line.remove(self.cursor);
+            }
+            Key::Left | Key::Ctrl('b') => self.cursor = self.cursor.saturating_sub(1),
+            Key::Right | Key::Ctrl('f') => self.cursor = (self.cursor + 1).min(self.line.len()),
+            Key::Home | Key::Ctrl('a') => self.cursor = 0,
+            Key::End | Key::Ctrl('e') => self.cursor = self.line.len(),
+            Key::WordLeft => self.cursor = self.word_start(),
+            Key::WordRight => self.cursor = self.word_end(),
+            Key::Up | Key::Ctrl('p') if self.position > 0 => self.recall(self.position - 1),
+            Key::Down | Key::Ctrl('n') if self.position < self.history.entries.len() => {
+                self.recall(self.position + 1)
+            }
+            Key::Ctrl('k') => self.line.truncate(self.cursor),
+            Key::Ctrl('u') => {
+                self.line.drain(..self.cursor);
+                self.cursor = 0;
+            }
+            Key::Ctrl('w') => {
+                let start = self.word_start();
+                self.line.drain(start..self.cursor);
+                self.cursor = start;
+            }
+            Key::Tab => self.complete(),
+            Key::Ctrl('l') => print!("\x1B[H\x1B[2J"),
+            Key::Ctrl('r') => {
+                self.search = Some(Search {
+                    query: String::new(),
+                    found: None,
+                })
+            }
+            _ => {}
+        }
+        None
+    }
+
+    fn search_key(&mut self, key: Key) -> Option<ReadLine> {
+        let search = self.search.as_mut()?;
+        let entries = self.history.entries.len();
+        match key {
+            Key::Char(c) => {
+                search.query.push(c);
+                // The current match is kept as long as it still matches
+                let before = search.found.map_or(entries, |found| found + 1);
+                search.found = self.history.search(&search.query, before);
+            }
+            Key::Backspace | Key::Ctrl('h') => {
+                search.query.pop();
+                search.found = self.history.search(&search.query, entries);
+            }
+            Key::Ctrl('r') => {
+                let before = search.found.unwrap_or(entries);
+                if let Some(found) = self.history.search(&search.query, before) {
+                    search.found = Some(found);
+                }
+            }
+            Key::Ctrl('c') | Key::Ctrl('g') => self.search = None,
+            // Any other key takes the match and goes on as when editing
+            _ => {
+                if let Some(found) = search.found {
+                    self.recall(found);
+                }
+                self.search = None;
+                return self.key(key);
+            }
+        }
+        None
+    }
+
+    // Extends the word before the cursor as far as all the candidates agree,
+    // or lists them if they already differ at the cursor
+    fn complete(&mut self) {
+        let before: String = self.line[..self.cursor].iter().collect();
+        let Some(Completion { start, candidates }) = (self.complete)(&before) else {
+            return;
+        };
+        let Some(first) = candidates.first() else {
+            return;
+        };
+        let common = candidates.iter().fold(first.as_str(), |common, candidate| {
+            let len = common
+                .char_indices()
+                .zip(candidate.chars())
+                .find(|((_, a), b)| a != b)
+                .map_or(common.len().min(candidate.len()), |((i, _), _)| i);
+            &common[..len]
+        });
+        let start = before[..start].chars().count();
+        if common.chars().count() > self.cursor - start {
+            self.line.splice(start..self.cursor, common.chars());
+            self.cursor = start + common.chars().count();
+        } else if candidates.len() > 1 {
+            println!();
+            println!("{}", candidates.join("  "));
+        }
+    }
+
+    // Replaces the line with a history entry, or the draft past the last one
+    fn recall(&mut self, position: usize) {
+        if self.position == self.history.entries.len() {
+            self.draft = std::mem::take(&mut self.line);
+        }
+        self.position = position;
+        self.line = match self.history.entries.get(position) {
+            Some(entry) => entry.chars().collect(),
+            None => self.draft.clone(),
+        };
+        self.cursor = self.line.len();
+    }
+
+    // Start of the word before the cursor
+    fn word_start(&self) -> usize {
+        let mut i = self.cursor;
+        while i > 0 && !is_word_char(self.line[i - 1]) {
+            i -= 1;
+        }
+        while i > 0 && is_word_char(self.line[i - 1]) {
+            i -= 1;
+        }
+        i
+    }
+
+    // End of the word after the cursor
+    fn word_end(&self) -> usize {
+        let mut i = self.cursor;
+        while i < self.line.len() && !is_word_char(self.line[i]) {
+            i += 1;
+        }
+        while i < self.line.len() && is_word_char(self.line[i]) {
+            i += 1;
+        }
+        i
+    }
+
+    // Redraws the line and puts the cursor back in place
+    fn render(&self) -> std::io::Result<()> {
+        let (prefix, text, cursor) = match &self.search {
+            Some(search) => {
+                let entry = search
+                    .found
+                    .map_or("", |found| &self.history.entries[found]);
+                let failed = if search.found.is_none() && !search.query.is_empty() {
+                    "failed "
+                } else {
+                    ""
+                };
+                let prefix = format!("({failed}reverse-i-search)`{}': ", search.query);
+                let cursor = entry.find(&search.query).unwrap_or(0);
+                let cursor = entry[..cursor].chars().count();
+                (prefix, entry.to_string(), cursor)
+            }
+            None => (
+                self.prompt.to_string(),
+                self.line.iter().collect(),
+                self.cursor,
+            ),
+        };
+        let column = prefix.chars().count() + cursor;
+        let mut out = std::io::stdout().lock();
+        write!(out, "\r{prefix}{}\x1B[K\r", highlight(&text))?;
+        if column > 0 {
+            write!(out, "\x1B[{column}C")?;
+        }
+        out.flush()
+    }
+
+    // Leaves the line as it was typed and moves to the next one
+    fn finish(&mut self, result: &ReadLine) -> std::io::Result<()> {
+        self.search = None;
+        self.cursor = self.line.len();
+        self.render()?;
+        match result {
+            ReadLine::Interrupted => println!("^C"),
+            ReadLine::Line(_) => println!(),
+            ReadLine::Eof => {}
+        }
+        std::io::stdout().flush()
+    }
+}
+
+fn is_word_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '_'
+}
+
+// Colors `text` token by token with the colors of source lines in error
+// messages. Characters the lexer rejects are shown in red
+fn highlight(text: &str) -> String {
+    let source = source::Source::from_string(text.to_string());
+    let tokens = match lexer::tokenise(&source) {
+        Ok(tokens) => tokens,
+        Err(error) => error.tokens,
+    };
+    let mut tokens = tokens.tokens.iter().peekable();
+    let mut highlighted = String::new();
+    let mut color = "";
+    for (i, c) in text.char_indices() {
+        while tokens.next_if(|token| token.span.end <= i).is_some() {}
+        let new_color = match tokens.peek() {
+            Some(token) if token.span.start <= i => lexer::token_color(&token.token_type),
+            _ if c.is_whitespace() => color,
+            _ => ";1;31",
+        };
+        if new_color != color {
+            highlighted.push_str(&format!("\x1B[{new_color}m"));
+            color = new_color;
+        }
+        highlighted.push(c);
+    }
+    highlighted.push_str("\x1B[m");
+    highlighted
+}
@@ -0,0 +1,71 @@
+use std::io::Write;
+use std::path::PathBuf;
+
+// Number of lines kept between sessions
+const MAX_ENTRIES: usize = 1000;
+
+// Lines entered so far, oldest first. They are kept in a file so that they
+// survive the session. The file is a convenience only: when it cannot be read
+// or written the history simply starts empty or is not saved
+#[derive(Debug, Default)]
+pub struct History {
+    pub entries: Vec<String>,
+    file: Option<PathBuf>,
+}
+
+impl History {
+    pub fn load() -> Self {
+        let Some(file) = history_file() else {
+            return Self::default();
+        };
+        let text = std::fs::read_to_string(&file).unwrap_or_default();
+        let mut entries: Vec<String> = text.lines().map(String::from).collect();
+        if entries.len() > MAX_ENTRIES {
+            entries.drain(..entries.len() - MAX_ENTRIES);
+            let _ = std::fs::write(&file, entries.join("\n") + "\n");
+        }
+        Self {
+            entries,
+            file: Some(file),
+        }
+    }
+
+    // Records a line, unless it is blank or repeats the previous one
+    pub fn add(&mut self, line: &str) {
+        if line.trim().is_empty() || self.entries.last().is_some_and(|last| last == line) {
+            return;
+        }
+        self.entries.push(line.to_string());
+        if let Some(file) = &self.file {
+            let _ = append_line(file, line);
+        }
+    }
+
+    // Index of the newest entry before `before` that contains `query`
+    pub fn search(&self, query: &str, before: usize) -> Option<usize> {
+        self.entries[..before]
+            .iter()
+            .rposition(|entry| entry.contains(query))
+    }
+}
+
+fn append_line(file: &PathBuf, line: &str) -> std::io::Result<()> {
+    if let Some(dir) = file.parent() {
+        std::fs::create_dir_all(dir)?;
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .create(true)
+        .append(true)
+        .open(file)?;
+    writeln!(file, "{line}")
+}
+
+// `$XDG_STATE_HOME/lambda-repl/history`, where `$XDG_STATE_HOME` defaults to
+// `~/.local/state`
+fn history_file() -> Option<PathBuf> {
+    let state_home = match std::env::var_os("XDG_STATE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME")?).join(".local/state"),
+    };
+    Some(state_home.join("lambda-repl").join("history"))
+}
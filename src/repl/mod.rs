@@ -0,0 +1,343 @@
+mod completion;
+mod editor;
+mod history;
+mod terminal;
+
+use crate::{debruijn, error, eval, interrupt, lexer, parser, source, types};
+
+use editor::{Editor, ReadLine};
+
+const PROMPT: &str = "λ> ";
+const CONTINUATION_PROMPT: &str = ".. ";
+
+// Names of the `:` commands
+const COMMANDS: &[&str] = &[
+    "ast", "church", "env", "load", "quit", "reload", "show", "strategy", "tokens", "trace",
+    "type", "typed",
+];
+
+pub struct Repl {
+    evaluator: eval::Evaluator,
+    editor: Editor,
+    // Files loaded with `:load`, in order, for `:reload`
+    loaded: Vec<String>,
+    // A signature line, waiting for the definition it applies to
+    signature: Option<String>,
+    quit: bool,
+}
+
+impl Repl {
+    pub fn new(evaluator: eval::Evaluator) -> Self {
+        Self {
+            evaluator,
+            editor: Editor::new(),
+            loaded: Vec::new(),
+            signature: None,
+            quit: false,
+        }
+    }
+
+    pub fn run(&mut self) {
+        // Ctrl-C stops the current evaluation rather than the whole session
+        if let Err(error) = interrupt::install() {
+            println!("Warning: Ctrl-C will end the session ({error})");
+        }
+
+        // Lines read so far for the current input
+        let mut input = String::new();
+        while !self.quit {
+            let prompt = if input.is_empty() {
+                PROMPT
+            } else {
+                CONTINUATION_PROMPT
+            };
+            let env = &self.evaluator.env;
+            let complete = |before: &str| completion::complete(env, &input, before);
+            match self.editor.read_line(prompt, &complete) {
+                Ok(ReadLine::Line(line)) => {
+                    // An empty line submits an incomplete input anyway
+                    let submit = !input.is_empty() && line.trim().is_empty();
+                    input.push_str(&line);
+                    input.push('\n');
+                    if submit || !needs_more_input(&input) {
+                        self.eval_line(std::mem::take(&mut input));
+                    }
+                }
+                // Ctrl-C drops what was typed so far
+                Ok(ReadLine::Interrupted) => input.clear(),
+                // End of input (Ctrl-D)
+                Ok(ReadLine::Eof) => {
+                    println!();
+                    if !input.is_empty() {
+                        self.eval_line(input);
+                    }
+                    break;
+                }
+                Err(error) => {
+                    println!("Error: could not read input ({error})");
+                    break;
+                }
+            }
+        }
+    }
+
+    fn eval_line(&mut self, input: String) {
+        if let Some(command) = input.trim().strip_prefix(':') {
+            self.run_command(command);
+            return;
+        }
+
+        let source = source::Source::from_string(input.clone());
+
+        // Tokenize
+        let tokens = match lexer::tokenise(&source) {
+            Ok(tokens) => tokens,
+            Err(error) => {
+                println!("{error}");
+                return;
+            }
+        };
+        if tokens.is_blank() {
+            return;
+        }
+
+        // Parse
+        let ast = match parser::parse_line(&tokens) {
+            Ok(ast) => ast,
+            Err(error) => {
+                println!("{error}");
+                return;
+            }
+        };
+
+        // A signature applies to the definition on the next line. The two are
+        // then read together, as they would be in a file
+        let signature = self.signature.take();
+        match (&*ast.ast, signature) {
+            (parser::ast::Ast::Signature(..), signature) => {
+                if let Some(signature) = signature {
+                    self.check_source(signature);
+                }
+                self.signature = Some(input);
+                return;
+            }
+            (parser::ast::Ast::Assign(..), Some(signature)) => {
+                self.check_source(signature + &input);
+                return;
+            }
+            (_, Some(signature)) => self.check_source(signature),
+            (_, None) => {}
+        }
+        self.run_ast(&tokens, &ast);
+    }
+
+    // Reads `input` as a file, for signatures and the definitions they apply
+    // to
+    fn check_source(&mut self, input: String) {
+        let source = source::Source::from_string(input);
+        let tokens = match lexer::tokenise(&source) {
+            Ok(tokens) => tokens,
+            Err(error) => {
+                println!("{error}");
+                return;
+            }
+        };
+        let (ast, errors) = parser::parse_file(&tokens);
+        if !errors.is_empty() {
+            println!("{errors}");
+            return;
+        }
+        self.run_ast(&tokens, &ast);
+    }
+
+    // Checks and evaluates a parsed line: its assignments are kept, an
+    // expression is evaluated
+    fn run_ast<'src>(
+        &mut self,
+        tokens: &'src lexer::TokenVec<'src>,
+        ast: &parser::ast::Node<'src>,
+    ) {
+        // Check types. Outside of typed mode those of assignments are only
+        // kept for `:type`
+        let is_assign = matches!(
+            *ast.ast,
+            parser::ast::Ast::Assign(..) | parser::ast::Ast::Source(_)
+        );
+        if self.evaluator.typed || is_assign {
+            let snapshot = self.evaluator.types.clone();
+            let diagnostics = types::check(tokens, ast, &mut self.evaluator.types);
+            if self.evaluator.typed {
+                print!("{diagnostics}");
+                // The definitions are rejected, so their old types still hold
+                if diagnostics.has_errors() {
+                    self.evaluator.types = snapshot;
+                    return;
+                }
+            }
+        }
+
+        // Assignments are kept for the following lines. They may refer to
+        // globals defined later on, so they are only checked when used
+        if is_assign {
+            let mut origins = eval::Origins::default();
+            if let Err(diagnostics) = self.evaluator.load(tokens, ast, &mut origins) {
+                print!("{diagnostics}");
+            }
+            return;
+        }
+
+        // Check scope
+        let env = &self.evaluator.env;
+        if let Err(error) = debruijn::lower(tokens, ast, |name| env.get(name).is_some()) {
+            println!("{error}");
+            return;
+        }
+
+        // Evaluate. A Ctrl-C pressed at the prompt must not stop it
+        interrupt::clear();
+        let mut origins = eval::Origins::default();
+        let term = eval::Term::from_ast(ast, &mut origins);
+        match self.evaluator.eval(&term) {
+            Ok(value) => println!("{value}"),
+            Err(error) => print!("{}", error.report(tokens, &origins, ast.span)),
+        }
+    }
+
+    fn run_command(&mut self, command: &str) {
+        let (name, argument) = command
+            .split_once(char::is_whitespace)
+            .map(|(name, argument)| (name, argument.trim()))
+            .unwrap_or((command, ""));
+        match name {
+            "load" if argument.is_empty() => println!("Error: expected a file name"),
+            "load" => {
+                if crate::load_file(&mut self.evaluator, argument, error::Format::Human, false) {
+                    self.loaded.retain(|file| file != argument);
+                    self.loaded.push(argument.to_string());
+                }
+            }
+            "reload" if self.loaded.is_empty() => println!("Error: no file loaded yet"),
+            "reload" => {
+                for file in &self.loaded {
+                    crate::load_file(&mut self.evaluator, file, error::Format::Human, false);
+                }
+            }
+            "env" => {
+                let env = &self.evaluator.env;
+                let mut names: Vec<&eval::Name> = env.names().collect();
+                names.sort();
+                for name in names {
+                    println!(
+                        "{name} = {}",
+                        env.get(name).expect("listed name is defined")
+                    );
+                }
+            }
+            "show" if argument.is_empty() => println!("Error: expected a name"),
+            "show" => match self.evaluator.env.get(argument) {
+                Some(value) => println!("{argument} = {value}"),
+                None => println!("Error: `{argument}` is not defined"),
+            },
+            "strategy" if argument.is_empty() => println!("{}", self.evaluator.strategy),
+            "strategy" => match argument.parse() {
+                Ok(strategy) => self.evaluator.strategy = strategy,
+                Err(error) => println!("Error: {error}"),
+            },
+            "trace" => switch(&mut self.evaluator.trace, argument),
+            "church" => switch(&mut self.evaluator.church, argument),
+            "typed" => switch(&mut self.evaluator.typed, argument),
+            "type" => self.show_type(argument),
+            "ast" => show_ast(argument),
+            "tokens" => show_tokens(argument),
+            "quit" => self.quit = true,
+            _ => println!("Error: unknown command `:{name}`"),
+        }
+    }
+
+    // Prints the principal type of the expression `input`, for `:type`
+    fn show_type(&mut self, input: &str) {
+        let source = source::Source::from_string(input.to_string());
+        let tokens = match lexer::tokenise(&source) {
+            Ok(tokens) => tokens,
+            Err(error) => {
+                println!("{error}");
+                return;
+            }
+        };
+        let ast = match parser::parse_line(&tokens) {
+            Ok(ast) => ast,
+            Err(error) => {
+                println!("{error}");
+                return;
+            }
+        };
+        if let parser::ast::Ast::Assign(..) | parser::ast::Ast::Signature(..) = *ast.ast {
+            println!("Error: expected an expression");
+            return;
+        }
+
+        let env = &self.evaluator.env;
+        if let Err(error) = debruijn::lower(&tokens, &ast, |name| env.get(name).is_some()) {
+            println!("{error}");
+            return;
+        }
+        match types::type_of(&tokens, &ast, &mut self.evaluator.types) {
+            Ok(ty) => println!("{} : {ty}", input.trim()),
+            Err(diagnostics) => print!("{diagnostics}"),
+        }
+    }
+}
+
+// Prints the tree `input` parses to, for `:ast`, after the source it would be
+// printed back as
+fn show_ast(input: &str) {
+    let source = source::Source::from_string(input.to_string());
+    let tokens = match lexer::tokenise(&source) {
+        Ok(tokens) => tokens,
+        Err(error) => {
+            println!("{error}");
+            return;
+        }
+    };
+    match parser::parse_line(&tokens) {
+        Ok(ast) => println!("{ast}\n{ast:#?}"),
+        Err(error) => println!("{error}"),
+    }
+}
+
+// Prints the tokens of `input`, for `:tokens`
+fn show_tokens(input: &str) {
+    let source = source::Source::from_string(input.to_string());
+    match lexer::tokenise(&source) {
+        Ok(tokens) => {
+            for token in &tokens.tokens {
+                println!("{token:?}");
+            }
+        }
+        Err(error) => println!("{error}"),
+    }
+}
+
+// Turns a setting `on` or `off`, or shows it if no argument is given
+fn switch(setting: &mut bool, argument: &str) {
+    match argument {
+        "" => println!("{}", if *setting { "on" } else { "off" }),
+        "on" => *setting = true,
+        "off" => *setting = false,
+        _ => println!("Error: expected `on` or `off`, found `{argument}`"),
+    }
+}
+
+// Whether `input` leaves parentheses unclosed or ends expecting more tokens, in
+// which case the REPL keeps reading lines. Lexer errors are reported right away.
+// Commands always fit on one line, their arguments are not lexed
+fn needs_more_input(input: &str) -> bool {
+    if input.trim_start().starts_with(':') {
+        return false;
+    }
+    let source = source::Source::from_string(input.to_string());
+    match lexer::tokenise(&source) {
+        Ok(tokens) => tokens.is_incomplete(),
+        Err(_) => false,
+    }
+}
@@ -0,0 +1,185 @@
+use std::ffi::c_int;
+use std::io::Read;
+
+const STDIN: c_int = 0;
+
+// Layout of `struct termios` in glibc on Linux
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Termios {
+    c_iflag: u32,
+    c_oflag: u32,
+    c_cflag: u32,
+    c_lflag: u32,
+    c_line: u8,
+    c_cc: [u8; 32],
+    c_ispeed: u32,
+    c_ospeed: u32,
+}
+
+const ICRNL: u32 = 0o400;
+const IXON: u32 = 0o2000;
+const ISIG: u32 = 0o1;
+const ICANON: u32 = 0o2;
+const ECHO: u32 = 0o10;
+const IEXTEN: u32 = 0o100000;
+const VTIME: usize = 5;
+const VMIN: usize = 6;
+const TCSADRAIN: c_int = 1;
+
+extern "C" {
+    fn tcgetattr(fd: c_int, termios: *mut Termios) -> c_int;
+    fn tcsetattr(fd: c_int, action: c_int, termios: *const Termios) -> c_int;
+}
+
+// Puts the terminal in raw mode for as long as it lives: keys are read one at
+// a time, without echo, and Ctrl-C arrives as a key instead of a signal. The
+// output is left alone, so `\n` still starts a new line
+pub struct RawMode {
+    original: Termios,
+}
+
+impl RawMode {
+    pub fn enable() -> std::io::Result<Self> {
+        // Safety: `Termios` matches the C layout, and is filled in by the call
+        let mut original: Termios = unsafe { std::mem::zeroed() };
+        if unsafe { tcgetattr(STDIN, &mut original) } != 0 {
+            return Err(std::io::Error::last_os_error());
+        }
+        let mut raw = original;
+        raw.c_iflag &= !(ICRNL | IXON);
+        raw.c_lflag &= !(ECHO | ICANON | ISIG | IEXTEN);
+        raw.c_cc[VMIN] = 1;
+        raw.c_cc[VTIME] = 0;
+        set_attributes(&raw)?;
+        Ok(Self { original })
+    }
+}
+
+impl Drop for RawMode {
+    fn drop(&mut self) {
+        // Nothing sensible can be done if the terminal cannot be restored
+        let _ = set_attributes(&self.original);
+    }
+}
+
+fn set_attributes(termios: &Termios) -> std::io::Result<()> {
+    // Safety: `termios` is a valid, initialised `Termios`
+    if unsafe { tcsetattr(STDIN, TCSADRAIN, termios) } != 0 {
+        return Err(std::io::Error::last_os_error());
+    }
+    Ok(())
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Key {
+    Char(char),
+    // Ctrl and a letter, given in lower case
+    Ctrl(char),
+    Enter,
+    Tab,
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    WordLeft,
+    WordRight,
+    Up,
+    Down,
+    Home,
+    End,
+    Unknown,
+}
+
+// Reads one key press, or `None` at the end of input
+pub fn read_key(input: &mut impl Read) -> std::io::Result<Option<Key>> {
+    let Some(byte) = read_byte(input)? else {
+        return Ok(None);
+    };
+    let key = match byte {
+        b'\r' | b'\n' => Key::Enter,
+        b'\t' => Key::Tab,
+        0x7f | 0x08 => Key::Backspace,
+        0x1b => read_escape(input)?,
+        0x01..=0x1a => Key::Ctrl(char::from(b'a' + byte - 1)),
+        0x00..=0x1f => Key::Unknown,
+        _ => read_char(input, byte)?,
+    };
+    Ok(Some(key))
+}
+
+fn read_byte(input: &mut impl Read) -> std::io::Result<Option<u8>> {
+    let mut byte = [0];
+    loop {
+        match input.read(&mut byte) {
+            Ok(0) => return Ok(None),
+            Ok(_) => return Ok(Some(byte[0])),
+            Err(error) if error.kind() == std::io::ErrorKind::Interrupted => continue,
+            Err(error) => return Err(error),
+        }
+    }
+}
+
+// Decodes the rest of a UTF-8 character starting with `first`
+fn read_char(input: &mut impl Read, first: u8) -> std::io::Result<Key> {
+    let len = match first {
+        0xc0..=0xdf => 2,
+        0xe0..=0xef => 3,
+        0xf0..=0xf7 => 4,
+        _ => 1,
+    };
+    let mut bytes = vec![first];
+    for _ in 1..len {
+        match read_byte(input)? {
+            Some(byte) => bytes.push(byte),
+            None => break,
+        }
+    }
+    Ok(std::str::from_utf8(&bytes)
+        .ok()
+        .and_then(|text| text.chars().next())
+        .map_or(Key::Unknown, Key::Char))
+}
+
+// Decodes the escape sequences sent by the usual terminals for the cursor and
+// editing keys. `Alt-b` and `Alt-f` move by words, like in readline
+fn read_escape(input: &mut impl Read) -> std::io::Result<Key> {
+    let key = match read_byte(input)? {
+        Some(b'[') => {
+            let mut params = String::new();
+            loop {
+                match read_byte(input)? {
+                    Some(byte @ (b'0'..=b'9' | b';')) => params.push(char::from(byte)),
+                    Some(byte) => break csi_key(&params, byte),
+                    None => break Key::Unknown,
+                }
+            }
+        }
+        Some(b'O') => match read_byte(input)? {
+            Some(b'H') => Key::Home,
+            Some(b'F') => Key::End,
+            _ => Key::Unknown,
+        },
+        Some(b'b') => Key::WordLeft,
+        Some(b'f') => Key::WordRight,
+        _ => Key::Unknown,
+    };
+    Ok(key)
+}
+
+fn csi_key(params: &str, last: u8) -> Key {
+    // Ctrl, Alt and their combinations with the arrows move by words
+    let modified = params.starts_with("1;");
+    match (params, last) {
+        (_, b'A') => Key::Up,
+        (_, b'B') => Key::Down,
+        (_, b'C') if modified => Key::WordRight,
+        (_, b'D') if modified => Key::WordLeft,
+        (_, b'C') => Key::Right,
+        (_, b'D') => Key::Left,
+        (_, b'H') | ("1" | "7", b'~') => Key::Home,
+        (_, b'F') | ("4" | "8", b'~') => Key::End,
+        ("3", b'~') => Key::Delete,
+        _ => Key::Unknown,
+    }
+}
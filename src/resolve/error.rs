@@ -0,0 +1,63 @@
+use super::*;
+
+use crate::error::{Diagnostic, Report, Severity};
+use crate::source;
+
+pub fn unbound_variable<'src>(span: lexer::Span<'src>, name: &str) -> Diagnostic<'src> {
+    Diagnostic::error("E0005", format!("unbound variable `{name}`"), span)
+}
+
+pub fn shadowed_definition<'src>(
+    span: lexer::Span<'src>,
+    earlier: lexer::Span<'src>,
+    name: &str,
+) -> Diagnostic<'src> {
+    Diagnostic::warning(
+        "W0001",
+        format!("`{name}` shadows its earlier definition"),
+        span,
+    )
+    .with_secondary(earlier, "first defined here")
+    .with_note("the last definition is the one in use")
+}
+
+pub fn unused_parameter<'src>(span: lexer::Span<'src>, name: &str) -> Diagnostic<'src> {
+    Diagnostic::warning("W0002", format!("unused parameter `{name}`"), span)
+}
+
+pub fn unused_binding<'src>(span: lexer::Span<'src>, name: &str) -> Diagnostic<'src> {
+    Diagnostic::warning("W0003", format!("unused binding `{name}`"), span)
+}
+
+#[derive(Debug)]
+pub struct ResolveDiagnosticVec<'src> {
+    pub tokens: &'src lexer::TokenVec<'src>,
+    pub diagnostics: Vec<Diagnostic<'src>>,
+}
+
+impl ResolveDiagnosticVec<'_> {
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+}
+
+impl Report for ResolveDiagnosticVec<'_> {
+    fn source(&self) -> &source::Source {
+        self.tokens.source
+    }
+
+    fn diagnostics(&self) -> &[Diagnostic<'_>] {
+        &self.diagnostics
+    }
+}
+
+impl std::fmt::Display for ResolveDiagnosticVec<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for diagnostic in &self.diagnostics {
+            diagnostic.print(f, self.tokens)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,159 @@
+mod error;
+
+use crate::{lexer, parser::ast};
+
+pub use error::ResolveDiagnosticVec;
+
+// Checks the names used by a whole file before it is evaluated. Definitions may
+// refer to each other in any order, and `is_global` tells about any other
+// global already in scope
+pub fn resolve<'src>(
+    tokens: &'src lexer::TokenVec<'src>,
+    ast: &ast::Node<'src>,
+    is_global: impl Fn(&str) -> bool,
+) -> ResolveDiagnosticVec<'src> {
+    let definitions: Vec<&ast::Node> = match &*ast.ast {
+        ast::Ast::Source(asts) => asts.iter().collect(),
+        _ => vec![ast],
+    };
+    let defined: Vec<&str> = definitions
+        .iter()
+        .filter_map(|ast| match &*ast.ast {
+            ast::Ast::Assign(name, _) => Some(*name),
+            _ => None,
+        })
+        .collect();
+
+    let mut resolver = Resolver {
+        tokens,
+        is_global: &|name| defined.contains(&name) || is_global(name),
+        bound: Vec::new(),
+        errors: 0,
+        diagnostics: Vec::new(),
+    };
+    let mut seen: Vec<&str> = Vec::new();
+    for definition in definitions {
+        match &*definition.ast {
+            ast::Ast::Assign(name, expr) => {
+                if let Some(&earlier) = seen.iter().find(|&&earlier| earlier == *name) {
+                    resolver.shadowed_definition(earlier, name);
+                }
+                seen.push(name);
+                resolver.resolve(expr);
+            }
+            _ => resolver.resolve(definition),
+        }
+    }
+
+    ResolveDiagnosticVec {
+        tokens,
+        diagnostics: resolver.diagnostics,
+    }
+}
+
+struct Resolver<'src, 'a> {
+    tokens: &'src lexer::TokenVec<'src>,
+    is_global: &'a dyn Fn(&'src str) -> bool,
+    // Parameters of the enclosing abstractions, innermost last, and whether
+    // they were used
+    bound: Vec<(&'src str, bool)>,
+    // Number of syntax error placeholders met so far
+    errors: usize,
+    diagnostics: Vec<crate::error::Diagnostic<'src>>,
+}
+
+impl<'src> Resolver<'src, '_> {
+    fn resolve(&mut self, expr: &ast::Node<'src>) {
+        match &*expr.ast {
+            ast::Ast::Var(name) => self.resolve_var(name, expr.span),
+            ast::Ast::Num(_) | ast::Ast::Fix => {}
+            ast::Ast::Not(expr) => self.resolve(expr),
+            ast::Ast::App(lhs, rhs)
+            | ast::Ast::Mul(lhs, rhs)
+            | ast::Ast::Div(lhs, rhs)
+            | ast::Ast::Add(lhs, rhs)
+            | ast::Ast::Sub(lhs, rhs)
+            | ast::Ast::And(lhs, rhs)
+            | ast::Ast::Or(lhs, rhs)
+            | ast::Ast::Eq(lhs, rhs)
+            | ast::Ast::Neq(lhs, rhs) => {
+                self.resolve(lhs);
+                self.resolve(rhs);
+            }
+            ast::Ast::If(condition, then, otherwise) => {
+                self.resolve(condition);
+                self.resolve(then);
+                self.resolve(otherwise);
+            }
+            ast::Ast::Abs(arg, _, body) => {
+                let errors = self.errors;
+                self.bound.push((arg, false));
+                self.resolve(body);
+                // The parameter may well be used in what could not be parsed
+                let unused = matches!(self.bound.pop(), Some((_, false)));
+                if unused && self.errors == errors {
+                    let span = lexer::Span::from_slice(&self.tokens.source.text, arg);
+                    self.diagnostics.push(error::unused_parameter(span, arg));
+                }
+            }
+            ast::Ast::Let(bindings, body) | ast::Ast::Where(body, bindings) => {
+                let depth = self.bound.len();
+                for binding in bindings {
+                    let (name, value) = binding.binding();
+                    self.resolve(value);
+                    self.bound.push((name, false));
+                }
+                self.resolve(body);
+                self.pop_bindings(depth);
+            }
+            // Every binding is in scope in all of the values
+            ast::Ast::LetRec(bindings, body) => {
+                let depth = self.bound.len();
+                for binding in bindings {
+                    self.bound.push((binding.binding().0, false));
+                }
+                for (i, binding) in bindings.iter().enumerate() {
+                    // A binding only used by its own value is still unused
+                    let used = self.bound[depth + i].1;
+                    self.resolve(binding.binding().1);
+                    self.bound[depth + i].1 = used;
+                }
+                self.resolve(body);
+                self.pop_bindings(depth);
+            }
+            ast::Ast::Assign(_, expr) => self.resolve(expr),
+            // The parser has already reported errors
+            ast::Ast::Error => self.errors += 1,
+            // Signatures are left to the type checker
+            ast::Ast::Signature(..) | ast::Ast::Comment(_) => {}
+            ast::Ast::Source(asts) => asts.iter().for_each(|ast| self.resolve(ast)),
+        }
+    }
+
+    // Drops the bindings of a `let` past the first `depth` names in scope,
+    // warning about those that were never used
+    fn pop_bindings(&mut self, depth: usize) {
+        for (name, used) in self.bound.split_off(depth) {
+            if !used {
+                let span = lexer::Span::from_slice(&self.tokens.source.text, name);
+                self.diagnostics.push(error::unused_binding(span, name));
+            }
+        }
+    }
+
+    fn resolve_var(&mut self, name: &'src str, span: lexer::Span<'src>) {
+        if let Some((_, used)) = self.bound.iter_mut().rev().find(|(arg, _)| *arg == name) {
+            *used = true;
+        } else if !(self.is_global)(name) {
+            self.diagnostics.push(error::unbound_variable(span, name));
+        }
+    }
+
+    fn shadowed_definition(&mut self, earlier: &'src str, name: &'src str) {
+        let source = &self.tokens.source;
+        let earlier = lexer::Span::from_slice(&source.text, earlier);
+        let span = lexer::Span::from_slice(&source.text, name);
+        self.diagnostics
+            .push(error::shadowed_definition(span, earlier, name));
+    }
+}
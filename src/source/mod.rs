@@ -46,9 +46,16 @@ impl Source {
     }
 }
 
-// Returns a vector of indices of line beginnings
+// Returns a vector of indices of line beginnings. There is always at least one
+// line, even if the text is empty
 fn find_lines(text: &str) -> Vec<usize> {
-    text.lines()
+    let lines: Vec<usize> = text
+        .lines()
         .map(|line| line.as_ptr() as usize - text.as_ptr() as usize)
-        .collect()
+        .collect();
+    if lines.is_empty() {
+        vec![0]
+    } else {
+        lines
+    }
 }
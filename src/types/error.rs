@@ -0,0 +1,112 @@
+use super::*;
+
+use crate::error::{Diagnostic, Report, Severity};
+use crate::source;
+
+pub fn mismatched_types<'src>(
+    span: lexer::Span<'src>,
+    expected: &str,
+    found: &str,
+) -> Diagnostic<'src> {
+    Diagnostic::error("E0006", "mismatched types".to_string(), span)
+        .with_label(format!("expected `{expected}`, found `{found}`"))
+}
+
+pub fn infinite_type<'src>(
+    span: lexer::Span<'src>,
+    subterm: &str,
+    var: &str,
+    ty: &str,
+) -> Diagnostic<'src> {
+    Diagnostic::error("E0007", format!("infinite type in {subterm}"), span)
+        .with_label(format!("`{var}` would have to be `{ty}`"))
+        .with_note("no type can contain itself")
+}
+
+pub fn unknown_type<'src>(span: lexer::Span<'src>, name: &str) -> Diagnostic<'src> {
+    Diagnostic::error("E0008", format!("unknown type `{name}`"), span).with_help(
+        "the base types are `Num` and `Bool`, type variables start with a lowercase letter",
+    )
+}
+
+pub fn missing_definition<'src>(span: lexer::Span<'src>, name: &str) -> Diagnostic<'src> {
+    Diagnostic::error(
+        "E0009",
+        format!("signature of `{name}` is not followed by its definition"),
+        span,
+    )
+    .with_help(format!("define `{name}` on the line after its signature"))
+}
+
+pub fn not_a_function<'src>(span: lexer::Span<'src>, ty: &str) -> Diagnostic<'src> {
+    Diagnostic::error(
+        "E0010",
+        format!("cannot apply a value of type `{ty}`"),
+        span,
+    )
+    .with_label("not a function")
+}
+
+pub fn not_comparable<'src>(span: lexer::Span<'src>, ty: &str) -> Diagnostic<'src> {
+    Diagnostic::error(
+        "E0019",
+        format!("cannot compare values of type `{ty}`"),
+        span,
+    )
+    .with_label("neither `Num` nor `Bool`")
+    .with_note("only numbers and booleans can be compared")
+}
+
+pub fn ill_typed<'src>(span: lexer::Span<'src>, name: &str) -> Diagnostic<'src> {
+    Diagnostic::error("E0020", format!("`{name}` has no type"), span)
+        .with_label("used here")
+        .with_note(format!("the definition of `{name}` does not type check"))
+}
+
+pub fn less_general<'src>(
+    span: lexer::Span<'src>,
+    signature: lexer::Span<'src>,
+    name: &str,
+    problem: String,
+) -> Diagnostic<'src> {
+    Diagnostic::error(
+        "E0011",
+        format!("`{name}` is less general than its signature"),
+        span,
+    )
+    .with_label(problem)
+    .with_secondary(signature, "type given here")
+}
+
+#[derive(Debug)]
+pub struct TypeDiagnosticVec<'src> {
+    pub tokens: &'src lexer::TokenVec<'src>,
+    pub diagnostics: Vec<Diagnostic<'src>>,
+}
+
+impl TypeDiagnosticVec<'_> {
+    pub fn has_errors(&self) -> bool {
+        self.diagnostics
+            .iter()
+            .any(|diagnostic| diagnostic.severity == Severity::Error)
+    }
+}
+
+impl Report for TypeDiagnosticVec<'_> {
+    fn source(&self) -> &source::Source {
+        self.tokens.source
+    }
+
+    fn diagnostics(&self) -> &[Diagnostic<'_>] {
+        &self.diagnostics
+    }
+}
+
+impl std::fmt::Display for TypeDiagnosticVec<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        for diagnostic in &self.diagnostics {
+            diagnostic.print(f, self.tokens)?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,926 @@
+mod error;
+
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+use crate::{eval::Name, graph, lexer, parser::ast};
+
+pub use error::TypeDiagnosticVec;
+
+// A type as the checker sees it. Variables stand for types not found out yet,
+// and get bound as unification learns about them
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Type {
+    Num,
+    Bool,
+    Var(usize),
+    Arrow(Rc<Type>, Rc<Type>),
+}
+
+// The type of a global, which may be used at any type its variables can be
+// replaced with
+#[derive(Debug, Clone)]
+pub struct Scheme {
+    vars: Vec<usize>,
+    // Those of `vars` that are compared, and so must be `Num` or `Bool`
+    comparable: Vec<usize>,
+    ty: Type,
+}
+
+impl Scheme {
+    // The type of a parameter, which is the same at every use
+    fn mono(ty: Type) -> Self {
+        Self {
+            vars: Vec::new(),
+            comparable: Vec::new(),
+            ty,
+        }
+    }
+}
+
+// Why two types could not be unified
+enum Mismatch {
+    Different,
+    // The variable would have to contain itself
+    Infinite(usize, Type),
+}
+
+// Types of the globals checked so far, kept from one source to the next
+#[derive(Debug, Default, Clone)]
+pub struct Env {
+    globals: HashMap<Name, Scheme>,
+    // Globals whose last definition did not check, and so have no type
+    ill_typed: HashSet<Name>,
+    // What each type variable is bound to, if anything yet
+    bindings: Vec<Option<Type>>,
+}
+
+impl Env {
+    fn fresh(&mut self) -> Type {
+        self.bindings.push(None);
+        Type::Var(self.bindings.len() - 1)
+    }
+
+    // Follows bound variables until a type that is not one
+    fn head(&self, ty: &Type) -> Type {
+        match ty {
+            Type::Var(var) => match &self.bindings[*var] {
+                Some(ty) => self.head(ty),
+                None => ty.clone(),
+            },
+            _ => ty.clone(),
+        }
+    }
+
+    // `ty` with every bound variable replaced, however deep
+    fn resolve(&self, ty: &Type) -> Type {
+        match self.head(ty) {
+            Type::Arrow(from, to) => {
+                Type::Arrow(Rc::new(self.resolve(&from)), Rc::new(self.resolve(&to)))
+            }
+            ty => ty,
+        }
+    }
+
+    fn occurs(&self, var: usize, ty: &Type) -> bool {
+        match self.head(ty) {
+            Type::Var(other) => other == var,
+            Type::Arrow(from, to) => self.occurs(var, &from) || self.occurs(var, &to),
+            Type::Num | Type::Bool => false,
+        }
+    }
+
+    // Makes `lhs` and `rhs` the same type, adding the variables it binds to
+    // `bound` so that they can be unbound again
+    fn unify(&mut self, lhs: &Type, rhs: &Type, bound: &mut Vec<usize>) -> Result<(), Mismatch> {
+        match (self.head(lhs), self.head(rhs)) {
+            (Type::Var(lhs), Type::Var(rhs)) if lhs == rhs => Ok(()),
+            (Type::Var(var), ty) | (ty, Type::Var(var)) => {
+                if self.occurs(var, &ty) {
+                    return Err(Mismatch::Infinite(var, ty));
+                }
+                self.bindings[var] = Some(ty);
+                bound.push(var);
+                Ok(())
+            }
+            (Type::Num, Type::Num) | (Type::Bool, Type::Bool) => Ok(()),
+            (Type::Arrow(lhs_from, lhs_to), Type::Arrow(rhs_from, rhs_to)) => {
+                self.unify(&lhs_from, &rhs_from, bound)?;
+                self.unify(&lhs_to, &rhs_to, bound)
+            }
+            _ => Err(Mismatch::Different),
+        }
+    }
+
+    // Variables left unbound in `ty`, in order of appearance
+    fn free_vars(&self, ty: &Type, vars: &mut Vec<usize>) {
+        match self.head(ty) {
+            Type::Var(var) if !vars.contains(&var) => vars.push(var),
+            Type::Arrow(from, to) => {
+                self.free_vars(&from, vars);
+                self.free_vars(&to, vars);
+            }
+            _ => {}
+        }
+    }
+
+    // Globals are generalized over all of their variables, as nothing else is
+    // in scope at the top of a source
+    fn generalize(&self, ty: &Type, comparable: &[usize]) -> Scheme {
+        let mut vars = Vec::new();
+        self.free_vars(ty, &mut vars);
+        Scheme {
+            comparable: comparable
+                .iter()
+                .copied()
+                .filter(|var| vars.contains(var))
+                .collect(),
+            vars,
+            ty: self.resolve(ty),
+        }
+    }
+
+    // A copy of the type of a global, with fresh variables. The copies of its
+    // comparable variables are returned as well
+    fn instantiate(&mut self, scheme: &Scheme) -> (Type, Vec<Type>) {
+        let fresh: Vec<(usize, Type)> =
+            scheme.vars.iter().map(|&var| (var, self.fresh())).collect();
+        let comparable = fresh
+            .iter()
+            .filter(|(var, _)| scheme.comparable.contains(var))
+            .map(|(_, ty)| ty.clone())
+            .collect();
+        (replace(&scheme.ty, &fresh), comparable)
+    }
+
+    // Writes out `types` for a message. Variables are named `a`, `b`, ... in
+    // order of appearance, the same across all of them
+    fn show(&self, types: &[&Type]) -> Vec<String> {
+        let mut vars = Vec::new();
+        types
+            .iter()
+            .map(|ty| {
+                let mut text = String::new();
+                self.write(&self.resolve(ty), &mut vars, &mut text);
+                text
+            })
+            .collect()
+    }
+
+    fn show_one(&self, ty: &Type) -> String {
+        let [text] = self.show(&[ty]).try_into().expect("one type shown");
+        text
+    }
+
+    fn write(&self, ty: &Type, vars: &mut Vec<usize>, text: &mut String) {
+        match ty {
+            Type::Num => text.push_str("Num"),
+            Type::Bool => text.push_str("Bool"),
+            Type::Var(var) => {
+                let index = vars
+                    .iter()
+                    .position(|other| other == var)
+                    .unwrap_or_else(|| {
+                        vars.push(*var);
+                        vars.len() - 1
+                    });
+                text.push(char::from(b'a' + (index % 26) as u8));
+                if index >= 26 {
+                    text.push_str(&(index / 26).to_string());
+                }
+            }
+            Type::Arrow(from, to) => {
+                let parens = matches!(**from, Type::Arrow(..));
+                text.push_str(if parens { "(" } else { "" });
+                self.write(from, vars, text);
+                text.push_str(if parens { ") -> " } else { " -> " });
+                self.write(to, vars, text);
+            }
+        }
+    }
+}
+
+// `ty` with the variables in `vars` replaced
+fn replace(ty: &Type, vars: &[(usize, Type)]) -> Type {
+    match ty {
+        Type::Var(var) => vars
+            .iter()
+            .find(|(other, _)| other == var)
+            .map_or_else(|| ty.clone(), |(_, ty)| ty.clone()),
+        Type::Arrow(from, to) => {
+            Type::Arrow(Rc::new(replace(from, vars)), Rc::new(replace(to, vars)))
+        }
+        Type::Num | Type::Bool => ty.clone(),
+    }
+}
+
+// A definition being checked: its position in the source, its body, its type
+// and the type variables named in its signature
+struct Definition<'src, 'a> {
+    index: usize,
+    name: &'src str,
+    expr: &'a ast::Node<'src>,
+    ty: Type,
+    rigid: Vec<(&'src str, Type)>,
+}
+
+// Checks the types of a whole source before it is evaluated, and adds those
+// of its definitions to `env`. Definitions may refer to each other in any
+// order: each group of them that does is checked together, after the
+// definitions it uses, and only then made polymorphic. A group with errors is
+// marked as ill-typed instead. Globals whose type is not known may be used at
+// any type
+pub fn check<'src>(
+    tokens: &'src lexer::TokenVec<'src>,
+    ast: &ast::Node<'src>,
+    env: &mut Env,
+) -> TypeDiagnosticVec<'src> {
+    let definitions: Vec<&ast::Node> = match &*ast.ast {
+        ast::Ast::Source(asts) => asts.iter().collect(),
+        _ => vec![ast],
+    };
+
+    let mut checker = Checker::new(tokens, env);
+
+    // A signature applies to the definition right after it
+    let mut signatures = vec![None; definitions.len()];
+    let mut waiting: Option<(&ast::Node, &str, &ast::Type)> = None;
+    for (index, definition) in definitions.iter().enumerate() {
+        match &*definition.ast {
+            ast::Ast::Signature(name, declared) => {
+                if let Some((signature, name, declared)) =
+                    waiting.replace((definition, name, declared))
+                {
+                    checker.missing_definition(signature, name, declared);
+                }
+            }
+            ast::Ast::Assign(name, _) => match waiting.take() {
+                Some((signature, other, declared)) if other == *name => {
+                    signatures[index] = Some((signature, declared));
+                }
+                Some((signature, name, declared)) => {
+                    checker.missing_definition(signature, name, declared);
+                }
+                None => {}
+            },
+            _ => {}
+        }
+    }
+    if let Some((signature, name, declared)) = waiting {
+        checker.missing_definition(signature, name, declared);
+    }
+
+    // A name defined twice stands for its last definition
+    let mut last: HashMap<&str, usize> = HashMap::new();
+    for (index, definition) in definitions.iter().enumerate() {
+        if let ast::Ast::Assign(name, _) = &*definition.ast {
+            last.insert(name, index);
+        }
+    }
+    let edges: Vec<Vec<usize>> = definitions
+        .iter()
+        .map(|definition| {
+            let expr = match &*definition.ast {
+                ast::Ast::Assign(_, expr) => expr,
+                ast::Ast::Signature(..) | ast::Ast::Comment(_) => return Vec::new(),
+                _ => definition,
+            };
+            let mut used = Vec::new();
+            globals_used(expr, &mut Vec::new(), &mut used);
+            used.iter()
+                .filter_map(|name| last.get(name).copied())
+                .collect()
+        })
+        .collect();
+
+    for group in graph::dependency_groups(&edges) {
+        checker.check_group(&group, &definitions, &signatures, &last);
+    }
+
+    checker.finish()
+}
+
+// The principal type of the expression `expr`, written out along with the
+// variables that must be numbers or booleans, as in
+// `a -> a -> Bool, where a is Num or Bool`
+pub fn type_of<'src>(
+    tokens: &'src lexer::TokenVec<'src>,
+    expr: &ast::Node<'src>,
+    env: &mut Env,
+) -> Result<String, TypeDiagnosticVec<'src>> {
+    let snapshot = env.clone();
+    let mut checker = Checker::new(tokens, env);
+    let ty = checker.infer(expr);
+    let text = checker.show_constrained(&ty);
+    let diagnostics = checker.finish();
+    *env = snapshot;
+    if diagnostics.has_errors() {
+        return Err(diagnostics);
+    }
+    Ok(text)
+}
+
+// Names of the globals `expr` refers to, that is those not bound by one of
+// its abstractions
+fn globals_used<'src>(
+    expr: &ast::Node<'src>,
+    bound: &mut Vec<&'src str>,
+    used: &mut Vec<&'src str>,
+) {
+    match &*expr.ast {
+        ast::Ast::Var(name) if !bound.contains(name) => used.push(name),
+        ast::Ast::Var(_) | ast::Ast::Num(_) | ast::Ast::Fix | ast::Ast::Error => {}
+        ast::Ast::Not(expr) => globals_used(expr, bound, used),
+        ast::Ast::App(lhs, rhs)
+        | ast::Ast::Mul(lhs, rhs)
+        | ast::Ast::Div(lhs, rhs)
+        | ast::Ast::Add(lhs, rhs)
+        | ast::Ast::Sub(lhs, rhs)
+        | ast::Ast::And(lhs, rhs)
+        | ast::Ast::Or(lhs, rhs)
+        | ast::Ast::Eq(lhs, rhs)
+        | ast::Ast::Neq(lhs, rhs) => {
+            globals_used(lhs, bound, used);
+            globals_used(rhs, bound, used);
+        }
+        ast::Ast::If(condition, then, otherwise) => {
+            globals_used(condition, bound, used);
+            globals_used(then, bound, used);
+            globals_used(otherwise, bound, used);
+        }
+        ast::Ast::Abs(arg, _, body) => {
+            bound.push(arg);
+            globals_used(body, bound, used);
+            bound.pop();
+        }
+        ast::Ast::Let(bindings, body) | ast::Ast::Where(body, bindings) => {
+            let depth = bound.len();
+            for binding in bindings {
+                let (name, value) = binding.binding();
+                globals_used(value, bound, used);
+                bound.push(name);
+            }
+            globals_used(body, bound, used);
+            bound.truncate(depth);
+        }
+        ast::Ast::LetRec(bindings, body) => {
+            let depth = bound.len();
+            bound.extend(bindings.iter().map(|binding| binding.binding().0));
+            for binding in bindings {
+                globals_used(binding.binding().1, bound, used);
+            }
+            globals_used(body, bound, used);
+            bound.truncate(depth);
+        }
+        ast::Ast::Assign(..)
+        | ast::Ast::Signature(..)
+        | ast::Ast::Comment(_)
+        | ast::Ast::Source(_) => {
+            unreachable!("assignments are not expressions")
+        }
+    }
+}
+
+// Where the type an expression was expected to have comes from, shown along
+// with a mismatch
+#[derive(Clone, Copy)]
+enum Reason<'src> {
+    // Found out along the way, if at all
+    Unknown,
+    // The operator of an arithmetic or logical operation
+    Operator(lexer::Span<'src>),
+    // The other side of a comparison
+    Operand(lexer::Span<'src>),
+    // The function an argument is given to
+    Function(lexer::Span<'src>),
+    Signature(lexer::Span<'src>),
+}
+
+struct Checker<'src, 'a> {
+    tokens: &'src lexer::TokenVec<'src>,
+    env: &'a mut Env,
+    // Parameters and local bindings in scope, innermost last
+    bound: Vec<(&'src str, Scheme)>,
+    // Definitions of the group being checked, which are not polymorphic yet
+    group: Vec<(&'src str, Type)>,
+    // Type variables named in the signature and annotations of the current
+    // definition
+    type_vars: Vec<(&'src str, Type)>,
+    // Where each variable was bound, to tell where a type comes from
+    origins: HashMap<usize, lexer::Span<'src>>,
+    // Types of the operands of `==` and `!=`, which are only known to be
+    // numbers or booleans once all of the group has been unified
+    compared: Vec<(lexer::Span<'src>, Type)>,
+    diagnostics: Vec<crate::error::Diagnostic<'src>>,
+}
+
+impl<'src, 'a> Checker<'src, 'a> {
+    fn new(tokens: &'src lexer::TokenVec<'src>, env: &'a mut Env) -> Self {
+        Self {
+            tokens,
+            env,
+            bound: Vec::new(),
+            group: Vec::new(),
+            type_vars: Vec::new(),
+            origins: HashMap::new(),
+            compared: Vec::new(),
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn finish(self) -> TypeDiagnosticVec<'src> {
+        TypeDiagnosticVec {
+            tokens: self.tokens,
+            diagnostics: self.diagnostics,
+        }
+    }
+
+    fn check_group(
+        &mut self,
+        group: &[usize],
+        definitions: &[&ast::Node<'src>],
+        signatures: &[Option<(&ast::Node<'src>, &ast::Type<'src>)>],
+        last: &HashMap<&str, usize>,
+    ) {
+        let errors = self.errors();
+        // Every definition gets its type up front, the declared one if any,
+        // so that the others can use it
+        let mut checked = Vec::new();
+        for &index in group {
+            let ast::Ast::Assign(name, expr) = &*definitions[index].ast else {
+                continue;
+            };
+            self.type_vars.clear();
+            let ty = match signatures[index] {
+                Some((_, declared)) => self.convert(declared),
+                None => self.env.fresh(),
+            };
+            if last[name] == index {
+                self.group.push((name, ty.clone()));
+            }
+            checked.push(Definition {
+                index,
+                name,
+                expr,
+                ty,
+                rigid: self.type_vars.clone(),
+            });
+        }
+
+        for definition in &checked {
+            self.type_vars = definition.rigid.clone();
+            let reason = match signatures[definition.index] {
+                Some((signature, _)) => Reason::Signature(signature.span),
+                None => Reason::Unknown,
+            };
+            self.expect(definition.expr, &definition.ty, reason);
+        }
+        // Bare expressions, as typed at the REPL
+        for &index in group {
+            if !matches!(
+                &*definitions[index].ast,
+                ast::Ast::Assign(..) | ast::Ast::Signature(..) | ast::Ast::Comment(_)
+            ) {
+                self.type_vars.clear();
+                self.infer(definitions[index]);
+            }
+        }
+        self.group.clear();
+        let comparable = self.check_comparable();
+
+        // Types found despite errors would only be guesses, so none are kept
+        let failed = self.errors() > errors;
+        for definition in checked {
+            let checks = match signatures[definition.index] {
+                Some((signature, _)) => !failed && self.check_rigid(&definition, signature.span),
+                None => !failed,
+            };
+            if last[definition.name] != definition.index {
+                continue;
+            }
+            let name = Name::from(definition.name);
+            if checks {
+                let scheme = self.env.generalize(&definition.ty, &comparable);
+                self.env.ill_typed.remove(&name);
+                self.env.globals.insert(name, scheme);
+            } else {
+                self.env.globals.remove(&name);
+                self.env.ill_typed.insert(name);
+            }
+        }
+    }
+
+    fn errors(&self) -> usize {
+        self.diagnostics
+            .iter()
+            .filter(|diagnostic| diagnostic.severity == crate::error::Severity::Error)
+            .count()
+    }
+
+    // Reports the compared types that turned out to be functions. Returns the
+    // variables that are still unknown, which may only become numbers or
+    // booleans
+    fn check_comparable(&mut self) -> Vec<usize> {
+        let mut comparable = Vec::new();
+        for (span, ty) in std::mem::take(&mut self.compared) {
+            match self.env.head(&ty) {
+                Type::Var(var) => comparable.push(var),
+                Type::Arrow(..) => {
+                    let text = self.env.show_one(&ty);
+                    self.diagnostics.push(error::not_comparable(span, &text));
+                }
+                Type::Num | Type::Bool => {}
+            }
+        }
+        comparable
+    }
+
+    // `ty` followed by its comparable variables, if it has any
+    fn show_constrained(&mut self, ty: &Type) -> String {
+        let mut vars = Vec::new();
+        self.env.free_vars(ty, &mut vars);
+        let mut comparable = self.check_comparable();
+        comparable.retain(|var| vars.contains(var));
+        comparable.sort_by_key(|var| vars.iter().position(|other| other == var));
+        comparable.dedup();
+
+        let types: Vec<Type> = comparable.iter().map(|&var| Type::Var(var)).collect();
+        let mut shown = self
+            .env
+            .show(&std::iter::once(ty).chain(&types).collect::<Vec<_>>());
+        let text = shown.remove(0);
+        match &shown[..] {
+            [] => text,
+            [var] => format!("{text}, where {var} is Num or Bool"),
+            [vars @ .., last] => {
+                format!(
+                    "{text}, where {} and {last} are Num or Bool",
+                    vars.join(", ")
+                )
+            }
+        }
+    }
+
+    // Whether the type variables named in the signature of `definition` were
+    // left unbound and apart from each other, as the signature promises
+    fn check_rigid(
+        &mut self,
+        definition: &Definition<'src, '_>,
+        signature: lexer::Span<'src>,
+    ) -> bool {
+        let rigid = &definition.rigid;
+        for (i, (var, ty)) in rigid.iter().enumerate() {
+            let head = self.env.head(ty);
+            let problem = match head {
+                Type::Var(_) => rigid[..i]
+                    .iter()
+                    .find(|(_, other)| self.env.head(other) == head)
+                    .map(|(other, _)| {
+                        format!("`{other}` and `{var}` would have to be the same type")
+                    }),
+                _ => Some(format!(
+                    "`{var}` would have to be `{}`",
+                    self.env.show_one(ty)
+                )),
+            };
+            if let Some(problem) = problem {
+                self.diagnostics.push(error::less_general(
+                    definition.expr.span,
+                    signature,
+                    definition.name,
+                    problem,
+                ));
+                return false;
+            }
+        }
+        true
+    }
+
+    fn infer(&mut self, expr: &ast::Node<'src>) -> Type {
+        match &*expr.ast {
+            ast::Ast::Var(name) => {
+                let scheme = match self.bound.iter().rev().find(|(arg, _)| arg == name) {
+                    Some((_, scheme)) => scheme.clone(),
+                    None => match self.group.iter().find(|(other, _)| other == name) {
+                        Some((_, ty)) => return ty.clone(),
+                        None => match self.env.globals.get(*name).cloned() {
+                            Some(scheme) => scheme,
+                            None if self.env.ill_typed.contains(*name) => {
+                                self.diagnostics.push(error::ill_typed(expr.span, name));
+                                return self.env.fresh();
+                            }
+                            None => return self.env.fresh(),
+                        },
+                    },
+                };
+                let (ty, comparable) = self.env.instantiate(&scheme);
+                self.compared
+                    .extend(comparable.into_iter().map(|ty| (expr.span, ty)));
+                ty
+            }
+            ast::Ast::Num(_) => Type::Num,
+            // `fix : (a -> a) -> a`
+            ast::Ast::Fix => {
+                let ty = Rc::new(self.env.fresh());
+                let function = Type::Arrow(ty.clone(), ty.clone());
+                Type::Arrow(Rc::new(function), ty)
+            }
+            ast::Ast::Not(operand) => {
+                let operator = self.between(expr.span.start, operand.outer.start);
+                self.expect(operand, &Type::Bool, Reason::Operator(operator));
+                Type::Bool
+            }
+            ast::Ast::Mul(lhs, rhs)
+            | ast::Ast::Div(lhs, rhs)
+            | ast::Ast::Add(lhs, rhs)
+            | ast::Ast::Sub(lhs, rhs) => {
+                let operator = self.between(lhs.outer.end, rhs.outer.start);
+                self.expect(lhs, &Type::Num, Reason::Operator(operator));
+                self.expect(rhs, &Type::Num, Reason::Operator(operator));
+                Type::Num
+            }
+            ast::Ast::And(lhs, rhs) | ast::Ast::Or(lhs, rhs) => {
+                let operator = self.between(lhs.outer.end, rhs.outer.start);
+                self.expect(lhs, &Type::Bool, Reason::Operator(operator));
+                self.expect(rhs, &Type::Bool, Reason::Operator(operator));
+                Type::Bool
+            }
+            // Both sides must be numbers or booleans, which is checked once
+            // the group is unified
+            ast::Ast::Eq(lhs, rhs) | ast::Ast::Neq(lhs, rhs) => {
+                let ty = self.infer(lhs);
+                self.expect(rhs, &ty, Reason::Operand(lhs.span));
+                self.compared.push((lhs.span, ty));
+                Type::Bool
+            }
+            // Both branches have the type of the first one
+            ast::Ast::If(condition, then, otherwise) => {
+                let keyword = self.between(expr.span.start, condition.outer.start);
+                self.expect(condition, &Type::Bool, Reason::Operator(keyword));
+                let ty = self.infer(then);
+                self.expect(otherwise, &ty, Reason::Operand(then.span));
+                ty
+            }
+            ast::Ast::App(function, arg) => {
+                let ty = self.infer(function);
+                match self.env.head(&ty) {
+                    Type::Arrow(from, to) => {
+                        self.expect(arg, &from, Reason::Function(function.span));
+                        (*to).clone()
+                    }
+                    Type::Var(_) => {
+                        let from = self.infer(arg);
+                        let to = self.env.fresh();
+                        let arrow = Type::Arrow(Rc::new(from), Rc::new(to.clone()));
+                        self.unify(expr.span, &ty, &arrow, Reason::Unknown);
+                        to
+                    }
+                    _ => {
+                        let mut diagnostic =
+                            error::not_a_function(function.span, &self.env.show_one(&ty));
+                        if let Some(origin) = self.origin(&ty) {
+                            let message = format!("this is `{}`", self.env.show_one(&ty));
+                            diagnostic = diagnostic.with_secondary(origin, message);
+                        }
+                        self.diagnostics.push(diagnostic);
+                        self.infer(arg);
+                        self.env.fresh()
+                    }
+                }
+            }
+            ast::Ast::Abs(arg, declared, body) => {
+                let from = match declared {
+                    Some(declared) => self.convert(declared),
+                    None => self.env.fresh(),
+                };
+                self.bound.push((arg, Scheme::mono(from.clone())));
+                let to = self.infer(body);
+                self.bound.pop();
+                Type::Arrow(Rc::new(from), Rc::new(to))
+            }
+            // Each binding may be used at different types in the next ones
+            // and in the body
+            ast::Ast::Let(bindings, body) | ast::Ast::Where(body, bindings) => {
+                let depth = self.bound.len();
+                for binding in bindings {
+                    let (name, value) = binding.binding();
+                    let ty = self.infer(value);
+                    let scheme = self.generalize_local(&ty);
+                    self.bound.push((name, scheme));
+                }
+                let ty = self.infer(body);
+                self.bound.truncate(depth);
+                ty
+            }
+            // The bindings have a single type within their values, as
+            // top-level definitions have within their group
+            ast::Ast::LetRec(bindings, body) => {
+                let depth = self.bound.len();
+                let types: Vec<Type> = bindings.iter().map(|_| self.env.fresh()).collect();
+                for (binding, ty) in bindings.iter().zip(&types) {
+                    self.bound
+                        .push((binding.binding().0, Scheme::mono(ty.clone())));
+                }
+                for (binding, ty) in bindings.iter().zip(&types) {
+                    self.expect(binding.binding().1, ty, Reason::Unknown);
+                }
+                self.bound.truncate(depth);
+                for (binding, ty) in bindings.iter().zip(&types) {
+                    let scheme = self.generalize_local(ty);
+                    self.bound.push((binding.binding().0, scheme));
+                }
+                let ty = self.infer(body);
+                self.bound.truncate(depth);
+                ty
+            }
+            // The parser has already reported it
+            ast::Ast::Error => self.env.fresh(),
+            ast::Ast::Assign(..)
+            | ast::Ast::Signature(..)
+            | ast::Ast::Comment(_)
+            | ast::Ast::Source(_) => {
+                unreachable!("assignments are not expressions")
+            }
+        }
+    }
+
+    // Local bindings are generalized over the variables that nothing else in
+    // scope mentions
+    fn generalize_local(&self, ty: &Type) -> Scheme {
+        let mut in_scope = Vec::new();
+        for (_, scheme) in &self.bound {
+            let mut vars = Vec::new();
+            self.env.free_vars(&scheme.ty, &mut vars);
+            in_scope.extend(vars.into_iter().filter(|var| !scheme.vars.contains(var)));
+        }
+        for (_, ty) in self.group.iter().chain(&self.type_vars) {
+            self.env.free_vars(ty, &mut in_scope);
+        }
+        let mut vars = Vec::new();
+        self.env.free_vars(ty, &mut vars);
+        vars.retain(|var| !in_scope.contains(var));
+        let comparable = self
+            .compared
+            .iter()
+            .filter_map(|(_, ty)| match self.env.head(ty) {
+                Type::Var(var) if vars.contains(&var) => Some(var),
+                _ => None,
+            })
+            .collect();
+        Scheme {
+            vars,
+            comparable,
+            ty: self.env.resolve(ty),
+        }
+    }
+
+    fn expect(&mut self, expr: &ast::Node<'src>, expected: &Type, reason: Reason<'src>) {
+        let found = self.infer(expr);
+        self.unify(expr.span, expected, &found, reason);
+    }
+
+    // Unifies the type `span` was expected to have with the one it has. When
+    // they do not match nothing is bound, so that both are reported as they
+    // were
+    fn unify(
+        &mut self,
+        span: lexer::Span<'src>,
+        expected: &Type,
+        found: &Type,
+        reason: Reason<'src>,
+    ) {
+        let mut bound = Vec::new();
+        match self.env.unify(expected, found, &mut bound) {
+            Ok(()) => {
+                for var in bound {
+                    self.origins.insert(var, span);
+                }
+            }
+            Err(mismatch) => {
+                for var in bound {
+                    self.env.bindings[var] = None;
+                }
+                let diagnostic = self.mismatch(span, mismatch, expected, found, reason);
+                self.diagnostics.push(diagnostic);
+            }
+        }
+    }
+
+    fn mismatch(
+        &self,
+        span: lexer::Span<'src>,
+        mismatch: Mismatch,
+        expected: &Type,
+        found: &Type,
+        reason: Reason<'src>,
+    ) -> crate::error::Diagnostic<'src> {
+        if let Mismatch::Infinite(var, ty) = mismatch {
+            let [var, ty] = self
+                .env
+                .show(&[&Type::Var(var), &ty])
+                .try_into()
+                .expect("two types shown");
+            return error::infinite_type(span, &self.subterm(span), &var, &ty);
+        }
+
+        let [expected_text, found_text] = self
+            .env
+            .show(&[expected, found])
+            .try_into()
+            .expect("two types shown");
+        let mut diagnostic = error::mismatched_types(span, &expected_text, &found_text);
+        // Where each side comes from, as far as it is known
+        let source = &self.tokens.source.text;
+        let expected_from = match reason {
+            Reason::Unknown => self
+                .origin(expected)
+                .map(|origin| (origin, format!("this is `{expected_text}`"))),
+            Reason::Operator(operator) => Some((
+                operator,
+                format!("`{}` takes `{expected_text}`", operator.get_text(source)),
+            )),
+            Reason::Operand(operand) => Some((operand, format!("this is `{expected_text}`"))),
+            Reason::Function(function) => Some((function, format!("this takes `{expected_text}`"))),
+            Reason::Signature(signature) => Some((signature, "type given here".to_string())),
+        };
+        let found_from = self
+            .origin(found)
+            .map(|origin| (origin, format!("this is `{found_text}`")));
+        for (origin, message) in expected_from.into_iter().chain(found_from) {
+            if (origin.start, origin.end) != (span.start, span.end) {
+                diagnostic = diagnostic.with_secondary(origin, message);
+            }
+        }
+        diagnostic
+    }
+
+    // Where the variable `ty` is was found to stand for something other than a
+    // variable, if it is one
+    fn origin(&self, ty: &Type) -> Option<lexer::Span<'src>> {
+        let mut origin = None;
+        let mut ty = ty.clone();
+        while let Type::Var(var) = ty {
+            ty = self.env.bindings[var].clone()?;
+            origin = self.origins.get(&var).copied();
+        }
+        origin
+    }
+
+    // The operator of an operation, which is what the source has between
+    // `start` and `end` besides whitespace
+    fn between(&self, start: usize, end: usize) -> lexer::Span<'src> {
+        let source = &self.tokens.source.text;
+        let text = lexer::Span::new(source, start, end).get_text(source).trim();
+        lexer::Span::from_slice(source, text)
+    }
+
+    // How to refer to the expression at `span` in a message
+    fn subterm(&self, span: lexer::Span<'src>) -> String {
+        let text = span.get_text(&self.tokens.source.text);
+        if text.contains('\n') || text.chars().count() > 40 {
+            "this expression".to_string()
+        } else {
+            format!("`{text}`")
+        }
+    }
+
+    // The type written in an annotation or a signature. Type variables are
+    // the same throughout a definition
+    fn convert(&mut self, ty: &ast::Type<'src>) -> Type {
+        match ty {
+            ast::Type::Name("Num") => Type::Num,
+            ast::Type::Name("Bool") => Type::Bool,
+            ast::Type::Name(name) if name.starts_with(|c: char| c.is_lowercase()) => {
+                if let Some((_, ty)) = self.type_vars.iter().find(|(other, _)| other == name) {
+                    return ty.clone();
+                }
+                let ty = self.env.fresh();
+                self.type_vars.push((name, ty.clone()));
+                ty
+            }
+            ast::Type::Name(name) => {
+                let span = lexer::Span::from_slice(&self.tokens.source.text, name);
+                self.diagnostics.push(error::unknown_type(span, name));
+                self.env.fresh()
+            }
+            ast::Type::Arrow(from, to) => {
+                let from = self.convert(from);
+                let to = self.convert(to);
+                Type::Arrow(Rc::new(from), Rc::new(to))
+            }
+        }
+    }
+
+    // The type is still looked at, for the names in it
+    fn missing_definition(
+        &mut self,
+        signature: &ast::Node<'src>,
+        name: &str,
+        declared: &ast::Type<'src>,
+    ) {
+        self.diagnostics
+            .push(error::missing_definition(signature.span, name));
+        self.type_vars.clear();
+        self.convert(declared);
+    }
+}